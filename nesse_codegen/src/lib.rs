@@ -16,14 +16,49 @@ const RENAME_LIST: &[(&str, &str)] = &[
 ];
 
 pub fn generate_opcode_list() -> Vec<NesOpcode> {
-    let basic_reference = Document::from(include_str!("..\\reference\\6502 Reference.html"));
+    let basic_reference = Document::from(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/reference/6502 Reference.html"
+    )));
     let mut opcodes: Vec<NesOpcode> = vec![];
     for section in basic_reference.find(Name("div")) {
         let mut opcode = read_opcode_definition(section);
         opcodes.append(&mut opcode);
     }
-    let extended_reference = include_str!("..\\reference\\undocumented.md");
-    let extended_opcodes: Vec<NesOpcode> = extended_reference
+    let extended_reference = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/reference/undocumented.md"
+    ));
+    let extended_opcodes = read_extended_reference(extended_reference, "*");
+    opcodes.extend_from_slice(&extended_opcodes);
+    opcodes
+    // extended_opcodes
+}
+
+/// the opcodes of the wdc 65c02: the documented nmos opcodes,
+/// with the additions and changes from the 65c02 reference on top
+pub fn generate_65c02_opcode_list() -> Vec<NesOpcode> {
+    let basic_reference = Document::from(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/reference/6502 Reference.html"
+    )));
+    let mut opcodes: Vec<NesOpcode> = vec![];
+    for section in basic_reference.find(Name("div")) {
+        let mut opcode = read_opcode_definition(section);
+        opcodes.append(&mut opcode);
+    }
+    let cmos_reference = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/reference/65c02.md"));
+    let cmos_opcodes = read_extended_reference(cmos_reference, "");
+    opcodes.retain(|op| !cmos_opcodes.iter().any(|cmos| cmos.opcode == op.opcode));
+    opcodes.extend_from_slice(&cmos_opcodes);
+    opcodes
+}
+
+/// reads opcodes from a reference in the nesdev wiki's style, e.g.
+/// `LAX (d,X) ($A3 dd; 6 cycles)`, with indented lines of description.
+/// the prefix is prepended to each name, to mark undocumented opcodes
+fn read_extended_reference(reference: &str, prefix: &str) -> Vec<NesOpcode> {
+    reference
         .split('\n')
         .filter(|line| !line.starts_with(' '))
         .filter(|line| line.len() > 1)
@@ -31,8 +66,8 @@ pub fn generate_opcode_list() -> Vec<NesOpcode> {
         //     println!("{}", line);
         // })
         .flat_map(|line| {
-            let (name, rest) = line.split_at(3);
-            let name = format!("*{}", name);
+            let (name, rest) = line.split_once(' ').unwrap();
+            let name = format!("{}{}", prefix, name);
             // println!("{}\t------------", name);
             let (addressing_string, rest) = rest.rsplit_once('(').unwrap();
             // println!("<{}>", addressing_string);
@@ -65,12 +100,24 @@ pub fn generate_opcode_list() -> Vec<NesOpcode> {
                 }
             })
         })
-        .collect();
-    opcodes.extend_from_slice(&extended_opcodes);
-    opcodes
-    // extended_opcodes
+        .collect()
 }
 
+/// implementations that replace the 2a03's on the nmos 6502, to honour the decimal flag
+pub const NMOS_OVERRIDES: &[(&str, &str)] = &[
+    ("adc", "adc_bcd"),
+    ("sbc", "sbc_bcd"),
+    ("isc", "isc_bcd"),
+    ("rra", "rra_bcd"),
+];
+
+/// implementations that replace the 2a03's on the 65c02
+pub const CMOS_OVERRIDES: &[(&str, &str)] = &[
+    ("adc", "adc_cmos"),
+    ("sbc", "sbc_cmos"),
+    ("jmp", "jmp_cmos"),
+];
+
 struct JumpListEntryGenerator {
     ident: Ident,
     addresssing: u8,
@@ -141,18 +188,12 @@ pub fn generate_opcode_name_list(known_opcodes: &[NesOpcode]) -> TokenStream {
 }
 
 fn generate_opcode_stub(name: String) -> TokenStream {
-    let ident = if name.len() == 3 {
-        Ident::new(&name, Span::call_site())
-    } else {
-        // extra opcodes include asterisk in name
-        let name: String = name.chars().filter(|c| c.is_ascii_alphabetic()).collect();
-        Ident::new(&name, Span::call_site())
-    };
+    let ident = opcode_ident(&name, &[]);
     quote! {
-        pub fn #ident(nes: &mut Nes, addressing: u8, cycles: u8, bytes: u8) -> u8 {
+        pub fn #ident<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, bytes: u8) -> u8 {
             println!("{} unimplemented", #name);
-            nes.cpu.running = false;
-            nes.cpu.registers.pc -= 1;
+            cpu.halt();
+            cpu.set_pc(cpu.get_pc().wrapping_sub(1));
             cycles
         }
     }
@@ -172,30 +213,37 @@ pub fn generate_stub_opcode_implementations(known_opcodes: &[NesOpcode]) -> Toke
     quote! {
         //! generated in nesse_codegen, in generate_generate_stub_opcode_implementations,
         //! edited by hand afterwards
-        use crate::{Nes, AddressableMemory};
+        use crate::cpu::Processor;
         #stubs
     }
 }
-pub fn generate_jumplist(known_opcodes: &[NesOpcode]) -> TokenStream {
+
+/// the name of the function implementing an opcode, after applying any overrides
+fn opcode_ident(name: &str, overrides: &[(&str, &str)]) -> Ident {
+    // extra opcodes include asterisk in name
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if let Some((_from, to)) = overrides.iter().find(|(from, _)| *from == name) {
+        Ident::new(to, Span::call_site())
+    } else {
+        Ident::new(&name, Span::call_site())
+    }
+}
+
+fn generate_jumplist_entries(
+    known_opcodes: &[NesOpcode],
+    overrides: &[(&str, &str)],
+) -> Vec<JumpListEntryGenerator> {
     let mut opcodes: Vec<JumpListEntryGenerator> = vec![];
     for opcode_number in 0i32..256 {
         if let Some(opcode) = known_opcodes
             .iter()
             .find(|op| op.opcode == opcode_number as u8)
         {
-            let ident = if opcode.meta.name.len() == 3 {
-                Ident::new(&opcode.meta.name.to_ascii_lowercase(), Span::call_site())
-            } else {
-                // extra opcodes include asterisk in name
-                let name: String = opcode
-                    .meta
-                    .name
-                    .chars()
-                    .filter(|c| c.is_ascii_alphabetic())
-                    .collect();
-                Ident::new(&name.to_ascii_lowercase(), Span::call_site())
-            };
-            // let ident = Ident::new(&opcode.meta.name.to_ascii_lowercase(), Span::call_site());
+            let ident = opcode_ident(&opcode.meta.name, overrides);
             let jle = JumpListEntryGenerator {
                 // index: opcode_number as u8,
                 ident,
@@ -215,14 +263,51 @@ pub fn generate_jumplist(known_opcodes: &[NesOpcode]) -> TokenStream {
             opcodes.push(jle);
         }
     }
+    opcodes
+}
+
+/// generates the jumptable for another 6502 variant, as an associated const on Jumptable
+pub fn generate_variant_jumplist(
+    known_opcodes: &[NesOpcode],
+    table_name: &str,
+    overrides: &[(&str, &str)],
+) -> TokenStream {
+    let opcodes = generate_jumplist_entries(known_opcodes, overrides);
+    let uses_placeholder = opcodes.iter().any(|op| op.ident == "placeholder");
+    let imports = if uses_placeholder {
+        quote!(
+            use crate::opcodes::jumptable::{placeholder, Jumptable, Opcode};
+        )
+    } else {
+        quote!(
+            use crate::opcodes::jumptable::{Jumptable, Opcode};
+        )
+    };
+    let table_name = Ident::new(table_name, Span::call_site());
+    quote! {
+        //! generated in nesse_codegen, in generate_variant_jumplist
+        use crate::cpu::Processor;
+        #imports
+        use crate::opcodes::*;
+        impl<P: Processor> Jumptable<P> {
+            pub const #table_name: [Opcode<P>;256] = [
+                #(Opcode {#opcodes},)*
+            ];
+        }
+    }
+}
+
+pub fn generate_jumplist(known_opcodes: &[NesOpcode]) -> TokenStream {
+    let opcodes = generate_jumplist_entries(known_opcodes, &[]);
     // we want to output code that looks like this:
     let template: TokenStream = quote! {
             //! generated in nesse_codegen, in generate_jumplist
-            use crate::Nes;
+            use crate::cpu::Processor;
             use crate::opcodes::*;
-            pub type OpcodeFn = fn(nes: &mut Nes, addressing: u8, cycles: u8, bytes: u8) -> u8;
-            pub struct Opcode {
-                pub exec: OpcodeFn,
+            use std::marker::PhantomData;
+            pub type OpcodeFn<P> = fn(cpu: &mut P, addressing: u8, cycles: u8, bytes: u8) -> u8;
+            pub struct Opcode<P> {
+                pub exec: OpcodeFn<P>,
                 pub addressing: u8,
                 pub cycles: u8,
                 pub bytes: u8,
@@ -230,22 +315,25 @@ pub fn generate_jumplist(known_opcodes: &[NesOpcode]) -> TokenStream {
 
             #[test]
             pub fn check_jumptable_entry_size() {
-                let entry_size = std::mem::size_of::<Opcode>();
+                let entry_size = std::mem::size_of::<Opcode<crate::Nes>>();
                 assert!(entry_size == std::mem::align_of::<usize>() * 2);
-                assert!(std::mem::size_of_val(&OPCODE_JUMPTABLE) == entry_size * 256);
+                assert!(std::mem::size_of_val(&Jumptable::<crate::Nes>::RICOH2A03) == entry_size * 256);
             }
 
-            impl Opcode {
+            impl<P> Opcode<P> {
                 #[inline(always)]
-                pub fn run(&self, nes: &mut Nes) -> u8 {
-                    (self.exec)(nes, self.addressing, self.cycles, self.bytes)
+                pub fn run(&self, cpu: &mut P) -> u8 {
+                    (self.exec)(cpu, self.addressing, self.cycles, self.bytes)
                 }
             }
-            pub const OPCODE_JUMPTABLE: [Opcode;256] = [
-                #(Opcode {#opcodes},)*
-            ];
+            pub struct Jumptable<P>(PhantomData<P>);
+            impl<P: Processor> Jumptable<P> {
+                pub const RICOH2A03: [Opcode<P>;256] = [
+                    #(Opcode {#opcodes},)*
+                ];
+            }
 
-            pub fn placeholder(nes: &mut Nes, addressing: u8, cycles: u8, bytes: u8) -> u8 {
+            pub fn placeholder<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, bytes: u8) -> u8 {
                 println!("opcode not implemented.");
                 0
             }
@@ -9,6 +9,17 @@ fn main() {
     let mut file = File::create("jumptable.rs").unwrap();
     file.write_all(jumplist.to_string().as_bytes()).unwrap();
 
+    let nmos_jumplist = generate_variant_jumplist(&known_opcodes, "NMOS6502", NMOS_OVERRIDES);
+    let mut file = File::create("jumptable_nmos.rs").unwrap();
+    file.write_all(nmos_jumplist.to_string().as_bytes())
+        .unwrap();
+
+    let cmos_opcodes = generate_65c02_opcode_list();
+    let cmos_jumplist = generate_variant_jumplist(&cmos_opcodes, "WDC65C02", CMOS_OVERRIDES);
+    let mut file = File::create("jumptable_65c02.rs").unwrap();
+    file.write_all(cmos_jumplist.to_string().as_bytes())
+        .unwrap();
+
     let function_placeholders = generate_stub_opcode_implementations(&known_opcodes);
     let mut file = File::create("stub_opcodes.rs").unwrap();
     file.write_all(function_placeholders.to_string().as_bytes())
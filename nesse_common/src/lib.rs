@@ -65,6 +65,10 @@ pub enum AddressingMode {
     Indirect,
     IndexedIndirect,
     IndirectIndexed,
+    /// (zp), 65c02 only
+    ZeroPageIndirect,
+    /// (a,x), 65c02 only
+    AbsoluteIndexedIndirect,
 }
 
 impl AddressingMode {
@@ -105,6 +109,13 @@ impl AddressingMode {
             "a,X" => AbsoluteX,
             "d,X" => ZeroPageX,
             "" => Implicit,
+            "A" => Accumulator,
+            "r" => Relative,
+            "(a)" => Indirect,
+            "(d)" => ZeroPageIndirect,
+            "(a,X)" => AbsoluteIndexedIndirect,
+            // zero page operand followed by a branch offset, for BBR and BBS
+            "d,r" => ZeroPage,
             _ => unimplemented!(),
         }
     }
@@ -124,6 +135,8 @@ impl AddressingMode {
             Indirect => 10,
             IndexedIndirect => 11,
             IndirectIndexed => 12,
+            ZeroPageIndirect => 13,
+            AbsoluteIndexedIndirect => 14,
         }
     }
     pub const fn from_u8(num: u8) -> AddressingMode {
@@ -142,6 +155,8 @@ impl AddressingMode {
             10 => Indirect,
             11 => IndexedIndirect,
             12 => IndirectIndexed,
+            13 => ZeroPageIndirect,
+            14 => AbsoluteIndexedIndirect,
             _ => Implicit,
         }
     }
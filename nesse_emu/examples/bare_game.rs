@@ -42,6 +42,7 @@ const LABEL_LIST: &[(u16, &str)] = &[
     (0x0000, "___"),
     (0x0000, "___"),
     (0x72d, "unknown"),
+    (0x0736, "read_controller"),
 ];
 
 #[rustfmt::skip]
@@ -79,7 +80,7 @@ const GAME_CODE: &[u8] = &[
     0x85, 0x01,
     0x60, // rts
     // 0x0638 loop
-    0x20, 0x4d, 0x06, // jsr readKeys
+    0x20, 0x36, 0x07, // jsr read_controller
     0x20, 0x8d, 0x06, // jsr checkCollision
     0x20, 0xc3, 0x06, // jsr updateSnake
     0x20, 0x19, 0x07, // jsr drawApple
@@ -202,6 +203,38 @@ const GAME_CODE: &[u8] = &[
     0xca, // dex
     0xd0, 0xfb, // bne spinloop
     0x60, // rts
+    // 0x0735 game over
+    0x00, // brk
+    // 0x0736 read_controller, turns the d-pad into the key readkeys looks for in 0xff
+    0xa9, 0x01, // lda 1
+    0x8d, 0x16, 0x40, // sta $4016 (strobe)
+    0xa9, 0x00, // lda 0
+    0x8d, 0x16, 0x40, // sta $4016
+    0xad, 0x16, 0x40, // lda $4016 (a)
+    0xad, 0x16, 0x40, // lda $4016 (b)
+    0xad, 0x16, 0x40, // lda $4016 (select)
+    0xad, 0x16, 0x40, // lda $4016 (start)
+    0xad, 0x16, 0x40, // lda $4016 (up)
+    0x29, 0x01, // and 1
+    0xf0, 0x04, // beq
+    0xa9, 0x77, // lda 'w'
+    0x85, 0xff, // sta 0xff
+    0xad, 0x16, 0x40, // lda $4016 (down)
+    0x29, 0x01, // and 1
+    0xf0, 0x04, // beq
+    0xa9, 0x73, // lda 's'
+    0x85, 0xff, // sta 0xff
+    0xad, 0x16, 0x40, // lda $4016 (left)
+    0x29, 0x01, // and 1
+    0xf0, 0x04, // beq
+    0xa9, 0x61, // lda 'a'
+    0x85, 0xff, // sta 0xff
+    0xad, 0x16, 0x40, // lda $4016 (right)
+    0x29, 0x01, // and 1
+    0xf0, 0x04, // beq
+    0xa9, 0x64, // lda 'd'
+    0x85, 0xff, // sta 0xff
+    0x4c, 0x4d, 0x06, // jmp readkeys
 ];
 
 fn main() {
@@ -224,7 +257,7 @@ fn main() {
         .unwrap();
 
     let mut random = RandomNumberGenerator(0xfe);
-    let mut input = KeyboardInput::new(0xff, event_pump);
+    let mut input = KeyboardInput::new(event_pump);
     let mut screen = SimpleScreen::new(0x200, texture, &mut canvas);
     let mut spy = Spy::new(LABEL_LIST);
     let mut rate = RateLimiter;
@@ -291,17 +324,23 @@ impl NesPeripheral for RandomNumberGenerator {
     }
 }
 
-pub struct KeyboardInput(u16, EventPump);
+pub struct KeyboardInput {
+    buttons: ButtonState,
+    event_pump: EventPump,
+}
 
 impl KeyboardInput {
-    pub fn new(mapped_address: u16, event_pump: EventPump) -> KeyboardInput {
-        KeyboardInput(mapped_address, event_pump)
+    pub fn new(event_pump: EventPump) -> KeyboardInput {
+        KeyboardInput {
+            buttons: ButtonState::default(),
+            event_pump,
+        }
     }
 }
 
 impl NesPeripheral for KeyboardInput {
     fn tick(&mut self, nes: &mut Nes) {
-        for event in self.1.poll_iter() {
+        for event in self.event_pump.poll_iter() {
             // println!("{:?}", event);
             match event {
                 Event::Quit { .. }
@@ -310,41 +349,36 @@ impl NesPeripheral for KeyboardInput {
                     ..
                 } => std::process::exit(0),
                 Event::KeyDown {
-                    keycode: Some(Keycode::W),
-                    ..
-                } => {
-                    // println!("pressed w, writing {} to {}", 0x77, self.0);
-                    nes.inject_memory_value(self.0, 0x77);
-                }
-                Event::KeyDown {
-                    keycode: Some(Keycode::S),
-                    ..
-                } => {
-                    // println!("pressed s, writing {} to {}", 0x73, self.0);
-                    nes.inject_memory_value(self.0, 0x73);
-                }
-                Event::KeyDown {
-                    keycode: Some(Keycode::A),
-                    ..
-                } => {
-                    // println!("pressed a, writing {} to {}", 0x61, self.0);
-                    nes.inject_memory_value(self.0, 0x61);
-                }
-                Event::KeyDown {
-                    keycode: Some(Keycode::D),
-                    ..
-                } => {
-                    // println!("pressed d, writing {} to {}", 0x64, self.0);
-                    nes.inject_memory_value(self.0, 0x64);
-                }
+                    keycode: Some(key), ..
+                } => press(&mut self.buttons, key, true),
+                Event::KeyUp {
+                    keycode: Some(key), ..
+                } => press(&mut self.buttons, key, false),
                 _ => {
-                    // ignore other keys
+                    // ignore other events
                 }
             }
         }
+        nes.set_buttons(0, self.buttons);
     }
 }
 
+/// wasd for the d-pad, k and j for a and b, enter for start and right shift for select
+fn press(buttons: &mut ButtonState, key: Keycode, pressed: bool) {
+    let button = match key {
+        Keycode::W => &mut buttons.up,
+        Keycode::S => &mut buttons.down,
+        Keycode::A => &mut buttons.left,
+        Keycode::D => &mut buttons.right,
+        Keycode::K => &mut buttons.a,
+        Keycode::J => &mut buttons.b,
+        Keycode::Return => &mut buttons.start,
+        Keycode::RShift => &mut buttons.select,
+        _ => return,
+    };
+    *button = pressed;
+}
+
 pub struct PCPrinter;
 
 impl NesPeripheral for PCPrinter {
@@ -380,13 +414,13 @@ impl<'a> SimpleScreen<'a> {
             canvas,
         }
     }
-    pub fn update_window(&mut self, nes: &Nes) {
+    pub fn update_window(&mut self, nes: &mut Nes) {
         let pixel_data = self.draw_frame(nes);
         self.texture.update(None, &pixel_data, 32 * 3).unwrap();
         self.canvas.copy(&self.texture, None, None).unwrap();
         self.canvas.present();
     }
-    pub fn draw_frame(&mut self, nes: &Nes) -> Vec<u8> {
+    pub fn draw_frame(&mut self, nes: &mut Nes) -> Vec<u8> {
         let mut frame = Vec::with_capacity(32 * 32 * 3);
         let buffer = nes.extract_memory_region(self.mapped_address, 32 * 32);
         for color in buffer.iter() {
@@ -428,7 +462,7 @@ impl<'a> NesPeripheral for SimpleScreen<'a> {
     fn cleanup(&mut self, _nes: &mut Nes) {}
 }
 
-fn memory_changed(nes: &Nes, address: u16, size: u16, old: &[u8]) -> bool {
+fn memory_changed(nes: &mut Nes, address: u16, size: u16, old: &[u8]) -> bool {
     for i in 0..size {
         if nes.extract_memory(i + address) != old[i as usize] {
             // println!("screen updated");
@@ -0,0 +1,61 @@
+use nesse_emu::peripherals::blargg::{run_test_rom, TestRomResult};
+use std::path::PathBuf;
+
+// runs test roms that report over blargg's $6000 protocol, e.g. the cpu, ppu, apu and mapper suites
+// cargo run --release --example blargg -- path/to/instr_test-v5/rom_singles
+// exits with an error if any rom didn't pass
+
+/// about 10 minutes of emulated time, more than any of the suites need
+const MAX_FRAMES: u32 = 60 * 60 * 10;
+
+fn main() {
+    let mut roms: Vec<PathBuf> = vec![];
+    for arg in std::env::args().skip(1) {
+        let path = PathBuf::from(arg);
+        if path.is_dir() {
+            match std::fs::read_dir(&path) {
+                Ok(entries) => roms.extend(
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .map(|entry| entry.path())
+                        .filter(|path| path.extension().is_some_and(|ext| ext == "nes")),
+                ),
+                Err(e) => println!("couldnt read directory {:?}: {}", path, e),
+            }
+        } else {
+            roms.push(path);
+        }
+    }
+    if roms.is_empty() {
+        println!("Expected rom files or directories provided as args");
+        return;
+    }
+    roms.sort();
+
+    let mut failures = 0;
+    for rom in roms.iter() {
+        let name = rom.display();
+        let result = std::fs::read(rom)
+            .ok()
+            .and_then(|bytes| run_test_rom(&bytes, MAX_FRAMES));
+        match result {
+            Some(TestRomResult::Passed(_)) => println!("PASS {}", name),
+            Some(TestRomResult::Failed(code, message)) => {
+                failures += 1;
+                println!("FAIL {} (code {}): {}", name, code, message);
+            }
+            Some(TestRomResult::Unfinished(message)) => {
+                failures += 1;
+                println!("DNF  {}: {}", name, message);
+            }
+            None => {
+                failures += 1;
+                println!("ERR  {}: couldnt load rom", name);
+            }
+        }
+    }
+    println!("{} of {} roms passed", roms.len() - failures, roms.len());
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
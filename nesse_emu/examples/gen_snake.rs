@@ -11,7 +11,7 @@ const CODE: &[u8] = &[
     0x20, 0x06, 0x86, 0x20, 0x38, 0x86, 0x20, 0x0d, 0x86, 0x20, 0x2a, 0x86, 0x60, 0xa9, 0x02, 0x85,
     0x02, 0xa9, 0x06, 0x85, 0x03, 0xa9, 0x11, 0x85, 0x10, 0xa9, 0x10, 0x85, 0x12, 0xa9, 0x0f, 0x85,
     0x14, 0xa9, 0x04, 0x85, 0x11, 0x85, 0x13, 0x85, 0x15, 0x60, 0xa5, 0xfe, 0x85, 0x00, 0xa5, 0xfe,
-    0x29, 0x03, 0x18, 0x69, 0x02, 0x85, 0x01, 0x60, 0x20, 0x4d, 0x86, 0x20, 0x8d, 0x86, 0x20, 0xc3,
+    0x29, 0x03, 0x18, 0x69, 0x02, 0x85, 0x01, 0x60, 0x20, 0x36, 0x87, 0x20, 0x8d, 0x86, 0x20, 0xc3,
     0x86, 0x20, 0x19, 0x87, 0x20, 0x20, 0x87, 0x20, 0x2d, 0x87, 0x4c, 0x38, 0x86, 0xa5, 0xff, 0xc9,
     0x77, 0xf0, 0x0d, 0xc9, 0x64, 0xf0, 0x14, 0xc9, 0x73, 0xf0, 0x1b, 0xc9, 0x61, 0xf0, 0x22, 0x60,
     0xa9, 0x04, 0x24, 0x02, 0xd0, 0x26, 0xa9, 0x01, 0x85, 0x02, 0x60, 0xa9, 0x08, 0x24, 0x02, 0xd0,
@@ -27,7 +27,14 @@ const CODE: &[u8] = &[
     0x01, 0x60, 0xe6, 0x11, 0xa9, 0x06, 0xc5, 0x11, 0xf0, 0x0c, 0x60, 0xc6, 0x10, 0xa5, 0x10, 0x29,
     0x1f, 0xc9, 0x1f, 0xf0, 0x01, 0x60, 0x4c, 0x35, 0x87, 0xa0, 0x00, 0xa5, 0xfe, 0x91, 0x00, 0x60,
     0xa6, 0x03, 0xa9, 0x00, 0x81, 0x10, 0xa2, 0x00, 0xa9, 0x01, 0x81, 0x10, 0x60, 0xa2, 0x00, 0xea,
-    0xea, 0xca, 0xd0, 0xfb, 0x60,
+    0xea, 0xca, 0xd0, 0xfb, 0x60, 0x00,
+    // 0x8736 read_controller: strobes $4016, skips a, b, select and start, then stores the key
+    // readkeys looks for in 0xff for each direction held, and jumps to readkeys
+    0xa9, 0x01, 0x8d, 0x16, 0x40, 0xa9, 0x00, 0x8d, 0x16, 0x40, 0xad, 0x16, 0x40, 0xad, 0x16, 0x40,
+    0xad, 0x16, 0x40, 0xad, 0x16, 0x40, 0xad, 0x16, 0x40, 0x29, 0x01, 0xf0, 0x04, 0xa9, 0x77, 0x85,
+    0xff, 0xad, 0x16, 0x40, 0x29, 0x01, 0xf0, 0x04, 0xa9, 0x73, 0x85, 0xff, 0xad, 0x16, 0x40, 0x29,
+    0x01, 0xf0, 0x04, 0xa9, 0x61, 0x85, 0xff, 0xad, 0x16, 0x40, 0x29, 0x01, 0xf0, 0x04, 0xa9, 0x64,
+    0x85, 0xff, 0x4c, 0x4d, 0x86,
 ];
 const INITIAL_PC: &[u8] = &[0x0, 0x86, 0, 0];
 
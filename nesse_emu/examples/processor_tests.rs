@@ -0,0 +1,207 @@
+use nesse_emu::prelude::*;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+// runs the single step tests from https://github.com/SingleStepTests/ProcessorTests
+// against a bare cpu on a flat 64KB ram, so nes mirroring stays out of the way.
+// cargo run --release --example processor_tests -- path/to/ProcessorTests/nes6502/v1 2a03
+// cargo run --release --example processor_tests -- path/to/ProcessorTests/6502/v1 nmos a9
+
+/// how many failing cases to print for each opcode
+const MAX_REPORTED_FAILURES: usize = 3;
+
+#[derive(Deserialize)]
+struct TestCase {
+    name: String,
+    initial: CpuState,
+    #[serde(rename = "final")]
+    expected: CpuState,
+    /// one entry per cycle: address, value, "read" or "write"
+    cycles: Vec<(u16, u8, String)>,
+}
+
+#[derive(Deserialize)]
+struct CpuState {
+    pc: u16,
+    s: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    p: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+impl CpuState {
+    fn registers(&self) -> NesRegisters {
+        NesRegisters {
+            pc: self.pc,
+            sp: self.s,
+            a: self.a,
+            x: self.x,
+            y: self.y,
+            p: self.p,
+        }
+    }
+}
+
+#[derive(Default)]
+struct OpcodeReport {
+    opcode: String,
+    passed: usize,
+    failed: usize,
+    failures: Vec<String>,
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let Some(directory) = args.next() else {
+        println!("Expected test directory provided as arg 1");
+        println!("usage: processor_tests <directory> [2a03|nmos|65c02] [opcode]");
+        return;
+    };
+    let variant = match args.next().as_deref() {
+        None | Some("2a03") => Variant::Ricoh2a03,
+        Some("nmos") => Variant::Nmos6502,
+        Some("65c02") => Variant::Wdc65c02,
+        Some(other) => {
+            println!("unknown variant: {}", other);
+            return;
+        }
+    };
+    let only_opcode = args.next().map(|opcode| opcode.to_ascii_lowercase());
+
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(&directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter(|path| match &only_opcode {
+                Some(opcode) => path.file_stem().is_some_and(|stem| stem == opcode.as_str()),
+                None => true,
+            })
+            .collect(),
+        Err(e) => {
+            println!("couldnt read directory {}: {}", directory, e);
+            return;
+        }
+    };
+    files.sort();
+
+    let mut cpu = Cpu6502::new(FlatRam::default()).with_variant(variant);
+    let mut reports = Vec::with_capacity(files.len());
+    for file in files.iter() {
+        let report = run_file(&mut cpu, file);
+        if report.failed == 0 {
+            println!("{}: {} passed", report.opcode, report.passed);
+        } else {
+            println!(
+                "{}: {} passed, {} failed",
+                report.opcode, report.passed, report.failed
+            );
+            for failure in report.failures.iter() {
+                println!("{}", failure);
+            }
+        }
+        reports.push(report);
+    }
+
+    let failing: Vec<&str> = reports
+        .iter()
+        .filter(|report| report.failed > 0)
+        .map(|report| report.opcode.as_str())
+        .collect();
+    let passed: usize = reports.iter().map(|report| report.passed).sum();
+    let failed: usize = reports.iter().map(|report| report.failed).sum();
+    println!(
+        "{} opcodes, {} cases passed, {} cases failed",
+        reports.len(),
+        passed,
+        failed
+    );
+    if !failing.is_empty() {
+        println!("failing opcodes: {}", failing.join(" "));
+        std::process::exit(1);
+    }
+}
+
+fn run_file(cpu: &mut Cpu6502<FlatRam>, file: &Path) -> OpcodeReport {
+    let opcode = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut report = OpcodeReport {
+        opcode,
+        ..Default::default()
+    };
+    let cases: Vec<TestCase> = match std::fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+    {
+        Ok(cases) => cases,
+        Err(e) => {
+            report.failed = 1;
+            report
+                .failures
+                .push(format!("  couldnt load {:?}: {}", file, e));
+            return report;
+        }
+    };
+    for case in cases.iter() {
+        let diffs = run_case(cpu, case);
+        if diffs.is_empty() {
+            report.passed += 1;
+        } else {
+            report.failed += 1;
+            if report.failures.len() < MAX_REPORTED_FAILURES {
+                report
+                    .failures
+                    .push(format!("  [{}] {}", case.name, diffs.join(", ")));
+            }
+        }
+    }
+    report
+}
+
+/// runs one instruction from the initial state, returning a description of each difference
+fn run_case(cpu: &mut Cpu6502<FlatRam>, case: &TestCase) -> Vec<String> {
+    cpu.inject_registers(case.initial.registers());
+    for (address, value) in case.initial.ram.iter() {
+        cpu.set_region(*address, &[*value]);
+    }
+
+    let cycles = cpu.step();
+
+    let mut diffs = vec![];
+    let actual = cpu.dump_registers();
+    let expected = case.expected.registers();
+    if actual.pc != expected.pc {
+        diffs.push(format!("pc {:04X} != {:04X}", actual.pc, expected.pc));
+    }
+    let registers = [
+        ("sp", actual.sp, expected.sp),
+        ("a", actual.a, expected.a),
+        ("x", actual.x, expected.x),
+        ("y", actual.y, expected.y),
+        ("p", actual.p, expected.p),
+    ];
+    for (name, actual, expected) in registers {
+        if actual != expected {
+            diffs.push(format!("{} {:02X} != {:02X}", name, actual, expected));
+        }
+    }
+    for (address, value) in case.expected.ram.iter() {
+        let actual = cpu.get(*address);
+        if actual != *value {
+            diffs.push(format!("${:04X} {:02X} != {:02X}", address, actual, value));
+        }
+    }
+    if cycles as usize != case.cycles.len() {
+        diffs.push(format!("cycles {} != {}", cycles, case.cycles.len()));
+    }
+
+    // clear everything the case touched so the next one starts from zeroed ram
+    for (address, _) in case.initial.ram.iter().chain(case.expected.ram.iter()) {
+        cpu.set(*address, 0);
+    }
+    diffs
+}
@@ -9,6 +9,7 @@ use sdl2::{
     EventPump, Sdl, VideoSubsystem,
 };
 
+// the snake game as written by gen_snake, which reads the d-pad through $4016
 const SNAKE_ROM: &[u8] = include_bytes!("snake.nes");
 
 fn main() {
@@ -31,7 +32,7 @@ fn main() {
         .unwrap();
 
     let mut random = RandomNumberGenerator(0xfe);
-    let mut input = KeyboardInput::new(0xff, event_pump);
+    let mut input = KeyboardInput::new(event_pump);
     let mut screen = SimpleScreen::new(0x200, texture, &mut canvas);
 
     let mut nes = Nes::default()
@@ -55,17 +56,23 @@ impl NesPeripheral for RandomNumberGenerator {
     }
 }
 
-pub struct KeyboardInput(u16, EventPump);
+pub struct KeyboardInput {
+    buttons: ButtonState,
+    event_pump: EventPump,
+}
 
 impl KeyboardInput {
-    pub fn new(mapped_address: u16, event_pump: EventPump) -> KeyboardInput {
-        KeyboardInput(mapped_address, event_pump)
+    pub fn new(event_pump: EventPump) -> KeyboardInput {
+        KeyboardInput {
+            buttons: ButtonState::default(),
+            event_pump,
+        }
     }
 }
 
 impl NesPeripheral for KeyboardInput {
     fn tick(&mut self, nes: &mut Nes) {
-        for event in self.1.poll_iter() {
+        for event in self.event_pump.poll_iter() {
             // println!("{:?}", event);
             match event {
                 Event::Quit { .. }
@@ -74,41 +81,36 @@ impl NesPeripheral for KeyboardInput {
                     ..
                 } => std::process::exit(0),
                 Event::KeyDown {
-                    keycode: Some(Keycode::W),
-                    ..
-                } => {
-                    // println!("pressed w, writing {} to {}", 0x77, self.0);
-                    nes.inject_memory_value(self.0, 0x77);
-                }
-                Event::KeyDown {
-                    keycode: Some(Keycode::S),
-                    ..
-                } => {
-                    // println!("pressed s, writing {} to {}", 0x73, self.0);
-                    nes.inject_memory_value(self.0, 0x73);
-                }
-                Event::KeyDown {
-                    keycode: Some(Keycode::A),
-                    ..
-                } => {
-                    // println!("pressed a, writing {} to {}", 0x61, self.0);
-                    nes.inject_memory_value(self.0, 0x61);
-                }
-                Event::KeyDown {
-                    keycode: Some(Keycode::D),
-                    ..
-                } => {
-                    // println!("pressed d, writing {} to {}", 0x64, self.0);
-                    nes.inject_memory_value(self.0, 0x64);
-                }
+                    keycode: Some(key), ..
+                } => press(&mut self.buttons, key, true),
+                Event::KeyUp {
+                    keycode: Some(key), ..
+                } => press(&mut self.buttons, key, false),
                 _ => {
-                    // ignore other keys
+                    // ignore other events
                 }
             }
         }
+        nes.set_buttons(0, self.buttons);
     }
 }
 
+/// wasd for the d-pad, k and j for a and b, enter for start and right shift for select
+fn press(buttons: &mut ButtonState, key: Keycode, pressed: bool) {
+    let button = match key {
+        Keycode::W => &mut buttons.up,
+        Keycode::S => &mut buttons.down,
+        Keycode::A => &mut buttons.left,
+        Keycode::D => &mut buttons.right,
+        Keycode::K => &mut buttons.a,
+        Keycode::J => &mut buttons.b,
+        Keycode::Return => &mut buttons.start,
+        Keycode::RShift => &mut buttons.select,
+        _ => return,
+    };
+    *button = pressed;
+}
+
 pub struct PCPrinter;
 
 impl NesPeripheral for PCPrinter {
@@ -144,13 +146,13 @@ impl<'a> SimpleScreen<'a> {
             canvas,
         }
     }
-    pub fn update_window(&mut self, nes: &Nes) {
+    pub fn update_window(&mut self, nes: &mut Nes) {
         let pixel_data = self.draw_frame(nes);
         self.texture.update(None, &pixel_data, 32 * 3).unwrap();
         self.canvas.copy(&self.texture, None, None).unwrap();
         self.canvas.present();
     }
-    pub fn draw_frame(&mut self, nes: &Nes) -> Vec<u8> {
+    pub fn draw_frame(&mut self, nes: &mut Nes) -> Vec<u8> {
         let mut frame = Vec::with_capacity(32 * 32 * 3);
         let buffer = nes.extract_memory_region(self.mapped_address, 32 * 32);
         for color in buffer.iter() {
@@ -192,7 +194,7 @@ impl<'a> NesPeripheral for SimpleScreen<'a> {
     fn cleanup(&mut self, _nes: &mut Nes) {}
 }
 
-fn memory_changed(nes: &Nes, address: u16, size: u16, old: &[u8]) -> bool {
+fn memory_changed(nes: &mut Nes, address: u16, size: u16, old: &[u8]) -> bool {
     for i in 0..size {
         if nes.extract_memory(i + address) != old[i as usize] {
             // println!("screen updated");
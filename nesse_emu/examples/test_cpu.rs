@@ -5,10 +5,9 @@ use std::time::Instant;
 const ROM: &[u8] = include_bytes!("nestest.nes");
 const LABEL_LIST: &[(u16, &str)] = &[];
 
-// use awk to strip cycles info:
+// prints the trace of the automated nestest run. the same trace is checked
+// against nestest.log line by line in `cargo test nestest`
 // cargo run --example test_cpu > out.txt
-// cat out.txt | awk '{print substr($0,0, 73)}' > out_nocycle.log
-// diff --left-column -W 154 -y out_nocycle.log nestest_nocycle.log > diff.txt
 
 fn main() {
     let snake_cartridge = NesCart::from_slice(&ROM).expect("constant rom failed to load");
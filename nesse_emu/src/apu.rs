@@ -0,0 +1,878 @@
+//! the 2a03's audio processing unit.
+//! the channel timers run off the cpu clock through Nes2a03Audio::tick, the envelopes,
+//! sweeps and length counters off the quarter and half frame clocks
+use crate::audio::Mixer;
+use crate::prelude::*;
+
+/// reads which channels are playing and the irqs, writes enable the channels
+pub const APU_STATUS: u16 = 0x4015;
+
+/// the 2a03's five channels, in the order the mixer takes their levels.
+/// expansion audio from cartridges is not emulated, so there are no channels for it yet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Pulse1,
+    Pulse2,
+    Triangle,
+    Noise,
+    Dmc,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 5] = [
+        Channel::Pulse1,
+        Channel::Pulse2,
+        Channel::Triangle,
+        Channel::Noise,
+        Channel::Dmc,
+    ];
+}
+
+/// a snapshot of one channel's registers and counters, for visualizers
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChannelState {
+    /// the timer period in cpu cycles, or as written for the pulses and triangle
+    pub period: u16,
+    /// the tone in hz, for the dmc the rate bits are played at
+    pub frequency: f32,
+    /// 0 to 3, only the pulses have a duty cycle
+    pub duty: u8,
+    /// the envelope's volume, 0 to 15, the triangle and dmc have none so this is 15 for them
+    pub volume: u8,
+    /// what is left of the length counter, or of the sample in bytes for the dmc
+    pub length_counter: u16,
+    pub playing: bool,
+    /// the level going into the mixer
+    pub output: u8,
+}
+
+/// length counter loads, picked by the top 5 bits of a channel's last register
+#[rustfmt::skip]
+const LENGTH_TABLE: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14,
+    12, 16, 24, 18, 48, 20, 96, 22, 192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+/// the pulse waveforms: 12.5%, 25%, 50% and 25% negated
+const DUTY_SEQUENCES: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0],
+    [0, 1, 1, 0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 1, 0, 0, 0],
+    [1, 0, 0, 1, 1, 1, 1, 1],
+];
+
+/// the triangle wave, stepped through from 15 down to 0 and back up
+#[rustfmt::skip]
+const TRIANGLE_SEQUENCE: [u8; 32] = [
+    15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0,
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+];
+
+/// the volume of a pulse or noise channel, either constant or a sawtooth fading from 15
+#[derive(Default, Clone, Copy)]
+struct Envelope {
+    /// set by writing the channel's last register, restarts the fade on the next clock
+    start: bool,
+    /// start again from 15 after reaching 0, shares a bit with the length counter halt flag
+    looping: bool,
+    constant: bool,
+    /// the constant volume, or how many quarter frames each step of the fade lasts
+    volume: u8,
+    divider: u8,
+    decay: u8,
+}
+
+impl Envelope {
+    /// the low 6 bits of the channel's first register
+    fn write(&mut self, value: u8) {
+        self.looping = value & 0b10_0000 > 0;
+        self.constant = value & 0b1_0000 > 0;
+        self.volume = value & 0b1111;
+    }
+    /// every quarter frame
+    fn clock(&mut self) {
+        if self.start {
+            self.start = false;
+            self.decay = 15;
+            self.divider = self.volume;
+        } else if self.divider == 0 {
+            self.divider = self.volume;
+            if self.decay > 0 {
+                self.decay -= 1;
+            } else if self.looping {
+                self.decay = 15;
+            }
+        } else {
+            self.divider -= 1;
+        }
+    }
+    fn output(&self) -> u8 {
+        if self.constant {
+            self.volume
+        } else {
+            self.decay
+        }
+    }
+}
+
+/// silences a channel after a set number of half frames, unless halted
+#[derive(Default, Clone, Copy)]
+struct LengthCounter {
+    /// cleared through $4015, which also keeps the counter at 0
+    enabled: bool,
+    halt: bool,
+    counter: u8,
+}
+
+impl LengthCounter {
+    /// loads the counter from the top 5 bits of a channel's last register
+    fn load(&mut self, value: u8) {
+        if self.enabled {
+            self.counter = LENGTH_TABLE[(value >> 3) as usize];
+        }
+    }
+    /// every half frame
+    fn clock(&mut self) {
+        if !self.halt && self.counter > 0 {
+            self.counter -= 1;
+        }
+    }
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.counter = 0;
+        }
+    }
+    fn active(&self) -> bool {
+        self.counter > 0
+    }
+}
+
+/// bends a pulse channel's period up or down every few half frames
+#[derive(Default, Clone, Copy)]
+struct Sweep {
+    enabled: bool,
+    /// half frames between adjustments, less one
+    period: u8,
+    negate: bool,
+    shift: u8,
+    /// set by writing the sweep register, restarts the divider on the next clock
+    reload: bool,
+    divider: u8,
+}
+
+impl Sweep {
+    /// the channel's second register
+    fn write(&mut self, value: u8) {
+        self.enabled = value & 0b1000_0000 > 0;
+        self.period = (value >> 4) & 0b111;
+        self.negate = value & 0b1000 > 0;
+        self.shift = value & 0b111;
+        self.reload = true;
+    }
+}
+
+/// one of the two square wave channels, at $4000-$4003 and $4004-$4007
+#[derive(Default, Clone, Copy)]
+pub struct Pulse {
+    /// pulse 1 negates its sweep with ones' complement, so it bends down one further than pulse 2
+    ones_complement: bool,
+    duty: u8,
+    /// position in the duty sequence, 0 to 7
+    step: u8,
+    /// the timer period, 11 bits
+    period: u16,
+    timer: u16,
+    envelope: Envelope,
+    sweep: Sweep,
+    length: LengthCounter,
+}
+
+impl Pulse {
+    /// pulse 1 has ones_complement set, pulse 2 does not
+    pub fn new(ones_complement: bool) -> Pulse {
+        Pulse {
+            ones_complement,
+            ..Default::default()
+        }
+    }
+    /// a write to one of the channel's four registers
+    fn write(&mut self, register: u16, value: u8) {
+        match register & 0b11 {
+            0 => {
+                self.duty = value >> 6;
+                self.length.halt = value & 0b10_0000 > 0;
+                self.envelope.write(value);
+            }
+            1 => self.sweep.write(value),
+            2 => self.period = (self.period & 0x700) | value as u16,
+            3 => {
+                self.period = (self.period & 0xff) | ((value as u16 & 0b111) << 8);
+                self.length.load(value);
+                self.step = 0;
+                self.envelope.start = true;
+            }
+            _ => unreachable!(),
+        }
+    }
+    /// every other cpu cycle
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            self.step = (self.step + 1) & 0b111;
+        } else {
+            self.timer -= 1;
+        }
+    }
+    /// the period the sweep unit is heading for, worked out all the time even when disabled
+    fn target_period(&self) -> u16 {
+        let change = self.period >> self.sweep.shift;
+        if !self.sweep.negate {
+            self.period + change
+        } else if self.ones_complement {
+            self.period.saturating_sub(change + 1)
+        } else {
+            self.period.saturating_sub(change)
+        }
+    }
+    /// too high or too low a period silences the channel, whether or not the sweep is enabled
+    fn muted(&self) -> bool {
+        self.period < 8 || self.target_period() > 0x7ff
+    }
+    /// every quarter frame
+    fn quarter_frame(&mut self) {
+        self.envelope.clock();
+    }
+    /// every half frame
+    fn half_frame(&mut self) {
+        self.length.clock();
+        let sweep = self.sweep;
+        if sweep.divider == 0 && sweep.enabled && sweep.shift > 0 && !self.muted() {
+            self.period = self.target_period();
+        }
+        if sweep.divider == 0 || sweep.reload {
+            self.sweep.divider = sweep.period;
+            self.sweep.reload = false;
+        } else {
+            self.sweep.divider -= 1;
+        }
+    }
+    /// the channel's level right now, 0 to 15
+    pub fn output(&self) -> u8 {
+        let high = DUTY_SEQUENCES[self.duty as usize][self.step as usize] > 0;
+        if high && self.length.active() && !self.muted() {
+            self.envelope.output()
+        } else {
+            0
+        }
+    }
+    /// the timer period, the tone is the cpu clock / (16 * (period + 1))
+    pub fn period(&self) -> u16 {
+        self.period
+    }
+    /// the envelope's volume, 0 to 15
+    pub fn volume(&self) -> u8 {
+        self.envelope.output()
+    }
+    /// the duty cycle, 0 to 3 for 12.5%, 25%, 50% and 25% negated
+    pub fn duty(&self) -> u8 {
+        self.duty
+    }
+    /// whether the length counter has not run out
+    pub fn playing(&self) -> bool {
+        self.length.active()
+    }
+    fn state(&self, region: Region) -> ChannelState {
+        ChannelState {
+            period: self.period,
+            frequency: (region.cpu_clock() / (16.0 * (self.period as f64 + 1.0))) as f32,
+            duty: self.duty,
+            volume: self.volume(),
+            length_counter: self.length.counter as u16,
+            playing: self.playing(),
+            output: self.output(),
+        }
+    }
+}
+
+/// the triangle channel at $4008-$400B, a fixed volume triangle wave
+#[derive(Default, Clone, Copy)]
+pub struct Triangle {
+    /// the length counter halt flag, which also keeps reloading the linear counter
+    control: bool,
+    /// loaded into the linear counter when it reloads
+    linear_reload: u8,
+    /// a second length counter, counting quarter frames
+    linear_counter: u8,
+    /// set by writing $400B, reloads the linear counter on the next quarter frame
+    linear_reload_flag: bool,
+    /// position in the triangle sequence, 0 to 31
+    step: u8,
+    /// the timer period, 11 bits
+    period: u16,
+    timer: u16,
+    length: LengthCounter,
+}
+
+impl Triangle {
+    /// a write to one of the channel's registers, $4009 is unused
+    fn write(&mut self, register: u16, value: u8) {
+        match register & 0b11 {
+            0 => {
+                self.control = value & 0b1000_0000 > 0;
+                self.length.halt = self.control;
+                self.linear_reload = value & 0b0111_1111;
+            }
+            1 => {}
+            2 => self.period = (self.period & 0x700) | value as u16,
+            3 => {
+                self.period = (self.period & 0xff) | ((value as u16 & 0b111) << 8);
+                self.length.load(value);
+                self.linear_reload_flag = true;
+            }
+            _ => unreachable!(),
+        }
+    }
+    /// every cpu cycle, the sequence only moves while both counters are running
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.linear_counter > 0 && self.length.active() {
+                self.step = (self.step + 1) & 0b1_1111;
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+    /// every quarter frame
+    fn quarter_frame(&mut self) {
+        if self.linear_reload_flag {
+            self.linear_counter = self.linear_reload;
+        } else if self.linear_counter > 0 {
+            self.linear_counter -= 1;
+        }
+        if !self.control {
+            self.linear_reload_flag = false;
+        }
+    }
+    /// every half frame
+    fn half_frame(&mut self) {
+        self.length.clock();
+    }
+    /// the channel's level right now, 0 to 15. stopping the counters holds the level
+    /// where it was instead of dropping to 0.
+    /// periods under 2 make an ultrasonic tone that a tv would average out,
+    /// so those play as the middle of the wave to save the pops
+    pub fn output(&self) -> u8 {
+        if self.period < 2 {
+            7
+        } else {
+            TRIANGLE_SEQUENCE[self.step as usize]
+        }
+    }
+    /// the timer period, the tone is the cpu clock / (32 * (period + 1))
+    pub fn period(&self) -> u16 {
+        self.period
+    }
+    /// whether the length counter has not run out
+    pub fn playing(&self) -> bool {
+        self.length.active()
+    }
+    fn state(&self, region: Region) -> ChannelState {
+        ChannelState {
+            period: self.period,
+            frequency: (region.cpu_clock() / (32.0 * (self.period as f64 + 1.0))) as f32,
+            duty: 0,
+            volume: 15,
+            length_counter: self.length.counter as u16,
+            playing: self.playing(),
+            output: self.output(),
+        }
+    }
+}
+
+/// the noise channel at $400C-$400F, pseudo random bits from a shift register
+#[derive(Clone, Copy)]
+pub struct Noise {
+    /// short mode feeds back from bit 6 instead of bit 1, for a 93 step metallic loop
+    short_mode: bool,
+    /// index into region.noise_periods()
+    period_index: u8,
+    timer: u16,
+    /// the 15 bit linear feedback shift register
+    shift_register: u16,
+    envelope: Envelope,
+    length: LengthCounter,
+}
+
+impl Default for Noise {
+    fn default() -> Noise {
+        Noise {
+            short_mode: false,
+            period_index: 0,
+            timer: 0,
+            shift_register: 1,
+            envelope: Envelope::default(),
+            length: LengthCounter::default(),
+        }
+    }
+}
+
+impl Noise {
+    /// a write to one of the channel's registers, $400D is unused
+    fn write(&mut self, register: u16, value: u8) {
+        match register & 0b11 {
+            0 => {
+                self.length.halt = value & 0b10_0000 > 0;
+                self.envelope.write(value);
+            }
+            1 => {}
+            2 => {
+                self.short_mode = value & 0b1000_0000 > 0;
+                self.period_index = value & 0b1111;
+            }
+            3 => {
+                self.length.load(value);
+                self.envelope.start = true;
+            }
+            _ => unreachable!(),
+        }
+    }
+    /// every cpu cycle, the periods are counted in cpu cycles
+    fn clock_timer(&mut self, region: Region) {
+        if self.timer == 0 {
+            self.timer = region.noise_periods()[self.period_index as usize] - 1;
+            let tap = if self.short_mode { 6 } else { 1 };
+            let feedback = (self.shift_register ^ (self.shift_register >> tap)) & 1;
+            self.shift_register = (self.shift_register >> 1) | (feedback << 14);
+        } else {
+            self.timer -= 1;
+        }
+    }
+    /// every quarter frame
+    fn quarter_frame(&mut self) {
+        self.envelope.clock();
+    }
+    /// every half frame
+    fn half_frame(&mut self) {
+        self.length.clock();
+    }
+    /// the channel's level right now, 0 to 15
+    pub fn output(&self) -> u8 {
+        if self.shift_register & 1 == 0 && self.length.active() {
+            self.envelope.output()
+        } else {
+            0
+        }
+    }
+    /// the timer period in cpu cycles
+    pub fn period(&self, region: Region) -> u16 {
+        region.noise_periods()[self.period_index as usize]
+    }
+    pub fn short_mode(&self) -> bool {
+        self.short_mode
+    }
+    /// the envelope's volume, 0 to 15
+    pub fn volume(&self) -> u8 {
+        self.envelope.output()
+    }
+    /// whether the length counter has not run out
+    pub fn playing(&self) -> bool {
+        self.length.active()
+    }
+    fn state(&self, region: Region) -> ChannelState {
+        let period = self.period(region);
+        ChannelState {
+            period,
+            frequency: (region.cpu_clock() / period as f64) as f32,
+            duty: 0,
+            volume: self.volume(),
+            length_counter: self.length.counter as u16,
+            playing: self.playing(),
+            output: self.output(),
+        }
+    }
+}
+
+/// the delta modulation channel at $4010-$4013, plays 1 bit delta samples from cpu memory.
+/// the nes fetches the sample bytes for it, see dma_request
+#[derive(Default, Clone, Copy)]
+pub struct Dmc {
+    irq_enabled: bool,
+    looping: bool,
+    /// index into region.dmc_rates()
+    rate_index: u8,
+    timer: u16,
+    /// the output level, 0 to 127
+    level: u8,
+    /// where samples start, $C000-$FFC0
+    sample_address: u16,
+    /// the sample length in bytes, 1 to 4081
+    sample_length: u16,
+    /// the memory reader's next address and how much of the sample is left
+    current_address: u16,
+    bytes_remaining: u16,
+    /// the byte fetched ahead for the output unit
+    sample_buffer: Option<u8>,
+    /// the byte being played, bit by bit, or none when the output unit is silent
+    shift_register: Option<u8>,
+    bits_remaining: u8,
+    /// set when a sample ends without looping, if irq_enabled
+    interrupt: bool,
+}
+
+impl Dmc {
+    /// a write to one of the channel's four registers
+    fn write(&mut self, register: u16, value: u8) {
+        match register & 0b11 {
+            0 => {
+                self.irq_enabled = value & 0b1000_0000 > 0;
+                if !self.irq_enabled {
+                    self.interrupt = false;
+                }
+                self.looping = value & 0b0100_0000 > 0;
+                self.rate_index = value & 0b1111;
+            }
+            1 => self.level = value & 0b0111_1111,
+            2 => self.sample_address = 0xc000 | (value as u16) << 6,
+            3 => self.sample_length = (value as u16) << 4 | 1,
+            _ => unreachable!(),
+        }
+    }
+    /// the channel's bit of $4015, which starts the sample if it had finished or stops it
+    fn set_enabled(&mut self, enabled: bool) {
+        self.interrupt = false;
+        if !enabled {
+            self.bytes_remaining = 0;
+        } else if self.bytes_remaining == 0 {
+            self.restart();
+        }
+    }
+    fn restart(&mut self) {
+        self.current_address = self.sample_address;
+        self.bytes_remaining = self.sample_length;
+    }
+    /// every cpu cycle, the rates are counted in cpu cycles
+    fn clock_timer(&mut self, region: Region) {
+        if self.timer == 0 {
+            self.timer = region.dmc_rates()[self.rate_index as usize] - 1;
+            self.clock_output();
+        } else {
+            self.timer -= 1;
+        }
+    }
+    /// moves the level up or down by 2 for each bit, then takes the next byte after 8 bits
+    fn clock_output(&mut self) {
+        if let Some(shift_register) = self.shift_register.as_mut() {
+            if *shift_register & 1 > 0 {
+                if self.level <= 125 {
+                    self.level += 2;
+                }
+            } else if self.level >= 2 {
+                self.level -= 2;
+            }
+            *shift_register >>= 1;
+        }
+        self.bits_remaining = self.bits_remaining.saturating_sub(1);
+        if self.bits_remaining == 0 {
+            self.bits_remaining = 8;
+            self.shift_register = self.sample_buffer.take();
+        }
+    }
+    /// the address the memory reader wants read, while the sample buffer is empty and
+    /// the sample is not finished. the cpu is halted for the read
+    pub fn dma_request(&self) -> Option<u16> {
+        if self.sample_buffer.is_none() && self.bytes_remaining > 0 {
+            Some(self.current_address)
+        } else {
+            None
+        }
+    }
+    /// hands the byte read for dma_request to the channel
+    pub fn dma_fill(&mut self, value: u8) {
+        self.sample_buffer = Some(value);
+        // the address wraps around to $8000, not $0000
+        self.current_address = self.current_address.checked_add(1).unwrap_or(0x8000);
+        self.bytes_remaining -= 1;
+        if self.bytes_remaining == 0 {
+            if self.looping {
+                self.restart();
+            } else if self.irq_enabled {
+                self.interrupt = true;
+            }
+        }
+    }
+    /// the channel's level right now, 0 to 127
+    pub fn output(&self) -> u8 {
+        self.level
+    }
+    /// the timer period in cpu cycles
+    pub fn rate(&self, region: Region) -> u16 {
+        region.dmc_rates()[self.rate_index as usize]
+    }
+    /// whether there is more of the sample to fetch
+    pub fn playing(&self) -> bool {
+        self.bytes_remaining > 0
+    }
+    /// whether the channel is asking for an irq
+    pub fn interrupt(&self) -> bool {
+        self.interrupt
+    }
+    fn state(&self, region: Region) -> ChannelState {
+        let rate = self.rate(region);
+        ChannelState {
+            period: rate,
+            frequency: (region.cpu_clock() / rate as f64) as f32,
+            duty: 0,
+            volume: 15,
+            length_counter: self.bytes_remaining,
+            playing: self.playing(),
+            output: self.output(),
+        }
+    }
+}
+
+/// the frame sequencer at $4017, which makes the quarter and half frame clocks and the
+/// frame irq, counting cpu cycles against region.frame_counter_steps()
+#[derive(Default, Clone, Copy)]
+struct FrameCounter {
+    five_step: bool,
+    irq_inhibit: bool,
+    interrupt: bool,
+    /// cpu cycles into the sequence
+    cycle: u32,
+    /// cpu cycles until a $4017 write restarts the sequence
+    restart_delay: u8,
+}
+
+impl FrameCounter {
+    /// a $4017 write, odd_cycle is whether it lands between apu cycles
+    fn write(&mut self, value: u8, odd_cycle: bool) {
+        self.five_step = value & 0b1000_0000 > 0;
+        self.irq_inhibit = value & 0b0100_0000 > 0;
+        if self.irq_inhibit {
+            self.interrupt = false;
+        }
+        self.restart_delay = if odd_cycle { 4 } else { 3 };
+    }
+    /// a cpu cycle, returns whether to clock the quarter and half frame units
+    fn tick(&mut self, region: Region) -> (bool, bool) {
+        let mut clocks = (false, false);
+        if self.restart_delay > 0 {
+            self.restart_delay -= 1;
+            if self.restart_delay == 0 {
+                self.cycle = 0;
+                // 5 step mode clocks everything as it restarts
+                if self.five_step {
+                    clocks = (true, true);
+                }
+            }
+        }
+        let steps = region.frame_counter_steps();
+        let cycle = self.cycle;
+        self.cycle += 1;
+        if cycle == steps[0] || cycle == steps[2] {
+            clocks.0 = true;
+        } else if cycle == steps[1] {
+            clocks = (true, true);
+        } else if !self.five_step {
+            // the irq flag is set on the last three cycles of the sequence
+            if cycle + 1 >= steps[3] && cycle <= steps[3] + 1 && !self.irq_inhibit {
+                self.interrupt = true;
+            }
+            if cycle == steps[3] {
+                clocks = (true, true);
+            } else if cycle == steps[3] + 1 {
+                // which is also the first cycle of the next sequence
+                self.cycle = 1;
+            }
+        } else if cycle == steps[4] {
+            clocks = (true, true);
+        } else if cycle == steps[4] + 1 {
+            self.cycle = 1;
+        }
+        clocks
+    }
+}
+
+/// the 2a03's audio registers at $4000-$4017 and the channels behind them
+pub struct Nes2a03Audio {
+    pub pulse_1: Pulse,
+    pub pulse_2: Pulse,
+    pub triangle: Triangle,
+    pub noise: Noise,
+    pub dmc: Dmc,
+    frame_counter: FrameCounter,
+    /// each channel's volume, mute and solo
+    pub mixer: Mixer,
+    /// picks the noise periods, set with Nes::set_console_region
+    pub region: Region,
+    /// cpu cycles since power on
+    pub cycles: u64,
+    /// what was last written to each register
+    registers: [u8; 0x18],
+}
+
+impl Default for Nes2a03Audio {
+    fn default() -> Nes2a03Audio {
+        Nes2a03Audio {
+            pulse_1: Pulse::new(true),
+            pulse_2: Pulse::new(false),
+            triangle: Triangle::default(),
+            noise: Noise::default(),
+            dmc: Dmc::default(),
+            frame_counter: FrameCounter::default(),
+            mixer: Mixer::default(),
+            region: Region::Ntsc,
+            cycles: 0,
+            registers: [0; 0x18],
+        }
+    }
+}
+
+impl Nes2a03Audio {
+    /// a single cpu cycle
+    pub fn tick(&mut self) {
+        let (quarter, half) = self.frame_counter.tick(self.region);
+        if quarter {
+            self.quarter_frame();
+        }
+        if half {
+            self.half_frame();
+        }
+        self.triangle.clock_timer();
+        self.noise.clock_timer(self.region);
+        self.dmc.clock_timer(self.region);
+        // the pulse timers count apu cycles, every other cpu cycle
+        if self.cycles & 1 == 1 {
+            self.pulse_1.clock_timer();
+            self.pulse_2.clock_timer();
+        }
+        self.cycles += 1;
+    }
+    /// clocks the envelopes
+    pub fn quarter_frame(&mut self) {
+        self.pulse_1.quarter_frame();
+        self.pulse_2.quarter_frame();
+        self.triangle.quarter_frame();
+        self.noise.quarter_frame();
+    }
+    /// clocks the length counters and sweeps
+    pub fn half_frame(&mut self) {
+        self.pulse_1.half_frame();
+        self.pulse_2.half_frame();
+        self.triangle.half_frame();
+        self.noise.half_frame();
+    }
+    /// whether the apu is holding the cpu's irq line
+    pub fn irq(&self) -> bool {
+        self.frame_counter.interrupt || self.dmc.interrupt()
+    }
+    /// whether the frame counter is asking for an irq
+    pub fn frame_interrupt(&self) -> bool {
+        self.frame_counter.interrupt
+    }
+    /// the reset button: the channels are silenced and the frame counter restarts as if
+    /// $4017 was written again
+    pub fn reset(&mut self) {
+        self.set(0x4015, 0);
+        let odd_cycle = self.cycles & 1 == 1;
+        self.frame_counter.write(self.registers[0x17], odd_cycle);
+        self.frame_counter.interrupt = false;
+    }
+    /// a $4015 read: which channels are playing and which irqs are pending.
+    /// bit 5 isn't driven and keeps the last value on the cpu's bus. reading clears the frame irq
+    pub fn status(&mut self, open_bus: u8) -> u8 {
+        let mut status = (open_bus & 0b0010_0000)
+            | self.pulse_1.playing() as u8
+            | (self.pulse_2.playing() as u8) << 1
+            | (self.triangle.playing() as u8) << 2
+            | (self.noise.playing() as u8) << 3
+            | (self.dmc.playing() as u8) << 4;
+        if self.frame_counter.interrupt {
+            status |= 0b0100_0000;
+        }
+        if self.dmc.interrupt() {
+            status |= 0b1000_0000;
+        }
+        self.frame_counter.interrupt = false;
+        status
+    }
+    /// each channel's level, in Channel::ALL order
+    fn levels(&self) -> [u8; 5] {
+        [
+            self.pulse_1.output(),
+            self.pulse_2.output(),
+            self.triangle.output(),
+            self.noise.output(),
+            self.dmc.output(),
+        ]
+    }
+    /// the mixed output of the channels, from 0 to about 1
+    pub fn output(&self) -> f32 {
+        self.mixer.mix(self.levels())
+    }
+    /// each channel's output on its own, in Channel::ALL order, for recording stems
+    pub fn stem_outputs(&self) -> [f32; 5] {
+        let levels = self.levels();
+        Channel::ALL.map(|channel| self.mixer.mix_alone(channel, levels[channel as usize]))
+    }
+    pub fn channel_state(&self, channel: Channel) -> ChannelState {
+        match channel {
+            Channel::Pulse1 => self.pulse_1.state(self.region),
+            Channel::Pulse2 => self.pulse_2.state(self.region),
+            Channel::Triangle => self.triangle.state(self.region),
+            Channel::Noise => self.noise.state(self.region),
+            Channel::Dmc => self.dmc.state(self.region),
+        }
+    }
+}
+
+impl Bus for Nes2a03Audio {
+    fn bounds(&self) -> (u16, u16) {
+        (0x4000, 0x401F)
+    }
+    fn set(&mut self, address: u16, value: u8) {
+        let offset = address.wrapping_sub(0x4000);
+        // println!("check: {} vs {}", address, offset);
+        if offset < 0x18 {
+            self.registers[offset as usize] = value;
+        } else {
+            panic!(
+                "attempted to set invalid memory range in apu. {:04X} = {:02X}",
+                address, value
+            );
+        }
+        match offset {
+            0x00..=0x03 => self.pulse_1.write(offset, value),
+            0x04..=0x07 => self.pulse_2.write(offset, value),
+            0x08..=0x0b => self.triangle.write(offset, value),
+            0x0c..=0x0f => self.noise.write(offset, value),
+            0x10..=0x13 => self.dmc.write(offset, value),
+            0x15 => {
+                // channel enables
+                self.pulse_1.length.set_enabled(value & 0b01 > 0);
+                self.pulse_2.length.set_enabled(value & 0b10 > 0);
+                self.triangle.length.set_enabled(value & 0b100 > 0);
+                self.noise.length.set_enabled(value & 0b1000 > 0);
+                self.dmc.set_enabled(value & 0b1_0000 > 0);
+            }
+            0x17 => self.frame_counter.write(value, self.cycles & 1 == 1),
+            _ => {}
+        }
+    }
+    fn get(&mut self, address: u16) -> u8 {
+        let offset = address.wrapping_sub(0x4000);
+        // println!("check: {} vs {}", address, offset);
+        if offset == 0x15 {
+            self.status(0)
+        } else if offset < 0x18 {
+            self.registers[offset as usize]
+        } else {
+            panic!(
+                "attempted to get invalid memory range in apu. {:04X}",
+                address
+            );
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//! getting sound out of the nes: the channel levels are mixed every cpu cycle, resampled
+//! down to the host's rate without aliasing, filtered like the console's output stage,
+//! and handed to an AudioSink once a frame
+use crate::apu::Channel;
+use crate::region::Region;
+
+/// output samples each level change is spread over
+const KERNEL_TAPS: usize = 16;
+/// fractions of an output sample a level change can land on
+const KERNEL_PHASES: usize = 32;
+/// the highest frequency kept, as a fraction of the output sample rate
+const CUTOFF: f64 = 0.45;
+/// the furthest dynamic rate control will stretch or squash the output
+const MAX_RATE_DELTA: f64 = 0.005;
+
+/// the 2a03's two output pins: the pulses share one, triangle, noise and dmc the other,
+/// and neither adds up linearly. looked up from tables rather than worked out each cycle,
+/// unless a channel's volume has been changed
+#[derive(Clone)]
+pub struct Mixer {
+    /// indexed by the sum of the pulse levels
+    pulse: [f32; 31],
+    /// indexed by 3 * triangle + 2 * noise + dmc
+    tnd: [f32; 203],
+    /// each channel's volume, 1 is as loud as on the console
+    volumes: [f32; 5],
+    muted: [bool; 5],
+    /// only this channel is heard
+    solo: Option<Channel>,
+}
+
+impl Default for Mixer {
+    fn default() -> Mixer {
+        let mut pulse = [0.0; 31];
+        for (n, level) in pulse.iter_mut().enumerate().skip(1) {
+            *level = 95.52 / (8128.0 / n as f32 + 100.0);
+        }
+        let mut tnd = [0.0; 203];
+        for (n, level) in tnd.iter_mut().enumerate().skip(1) {
+            *level = 163.67 / (24329.0 / n as f32 + 100.0);
+        }
+        Mixer {
+            pulse,
+            tnd,
+            volumes: [1.0; 5],
+            muted: [false; 5],
+            solo: None,
+        }
+    }
+}
+
+impl Mixer {
+    /// the output of the channels, from 0 to about 1, given their levels in Channel::ALL order
+    pub fn mix(&self, levels: [u8; 5]) -> f32 {
+        let gains = Channel::ALL.map(|channel| self.gain(channel));
+        if gains.iter().all(|gain| *gain == 1.0) {
+            let pulse = (levels[0] + levels[1]) as usize;
+            let tnd = 3 * levels[2] as usize + 2 * levels[3] as usize + levels[4] as usize;
+            self.pulse[pulse] + self.tnd[tnd]
+        } else {
+            let level = |n: usize| levels[n] as f32 * gains[n];
+            let pulse = level(0) + level(1);
+            let tnd = 3.0 * level(2) + 2.0 * level(3) + level(4);
+            let pulse = if pulse > 0.0 {
+                95.52 / (8128.0 / pulse + 100.0)
+            } else {
+                0.0
+            };
+            let tnd = if tnd > 0.0 {
+                163.67 / (24329.0 / tnd + 100.0)
+            } else {
+                0.0
+            };
+            pulse + tnd
+        }
+    }
+    /// a channel's output as if the others were silent, ignoring volume, mute and solo
+    pub fn mix_alone(&self, channel: Channel, level: u8) -> f32 {
+        let level = level as usize;
+        match channel {
+            Channel::Pulse1 | Channel::Pulse2 => self.pulse[level],
+            Channel::Triangle => self.tnd[3 * level],
+            Channel::Noise => self.tnd[2 * level],
+            Channel::Dmc => self.tnd[level],
+        }
+    }
+    /// how much of a channel is heard, after volume, mute and solo
+    pub fn gain(&self, channel: Channel) -> f32 {
+        let n = channel as usize;
+        if self.muted[n] || self.solo.is_some_and(|solo| solo != channel) {
+            0.0
+        } else {
+            self.volumes[n]
+        }
+    }
+    pub fn volume(&self, channel: Channel) -> f32 {
+        self.volumes[channel as usize]
+    }
+    /// sets a channel's volume, 1 is as loud as on the console
+    pub fn set_volume(&mut self, channel: Channel, volume: f32) {
+        self.volumes[channel as usize] = volume.max(0.0);
+    }
+    pub fn muted(&self, channel: Channel) -> bool {
+        self.muted[channel as usize]
+    }
+    pub fn set_muted(&mut self, channel: Channel, muted: bool) {
+        self.muted[channel as usize] = muted;
+    }
+    pub fn solo(&self) -> Option<Channel> {
+        self.solo
+    }
+    /// plays only one channel, or all of them again with None
+    pub fn set_solo(&mut self, solo: Option<Channel>) {
+        self.solo = solo;
+    }
+}
+
+/// receives the sound as the nes makes it, e.g. an audio device, a wav writer or a test
+pub trait AudioSink {
+    /// the rate the sink plays at, e.g. 48000
+    fn sample_rate(&self) -> u32;
+    /// 1 for mono, 2 for stereo, the same sound goes to every channel
+    fn channels(&self) -> usize {
+        1
+    }
+    /// called once a frame with interleaved samples from -1 to 1
+    fn samples(&mut self, samples: &[f32]);
+    /// whether to also make a recording of each channel on its own, see stem
+    fn wants_stems(&self) -> bool {
+        false
+    }
+    /// called once a frame for each channel, when wants_stems, with samples of that
+    /// channel alone. stems ignore the mixer's volume, mute and solo
+    fn stem(&mut self, _channel: Channel, _samples: &[f32]) {}
+    /// how full the sink's queue is, from 0 to 1. when given, the output is stretched or
+    /// squashed by up to half a percent to keep it half full, so a frontend can time
+    /// itself by its audio without the queue running dry or piling up
+    fn fill_level(&self) -> Option<f32> {
+        None
+    }
+}
+
+/// converts samples for a sink that wants 16 bit integers
+pub fn samples_to_i16(samples: &[f32], out: &mut Vec<i16>) {
+    out.clear();
+    out.extend(
+        samples
+            .iter()
+            .map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16),
+    );
+}
+
+/// a first order filter
+#[derive(Clone, Copy)]
+struct Filter {
+    high_pass: bool,
+    alpha: f32,
+    previous_in: f32,
+    previous_out: f32,
+}
+
+impl Filter {
+    fn new(high_pass: bool, frequency: f64, sample_rate: u32) -> Filter {
+        let rc = 1.0 / (2.0 * std::f64::consts::PI * frequency);
+        let dt = 1.0 / sample_rate as f64;
+        let alpha = if high_pass {
+            rc / (rc + dt)
+        } else {
+            dt / (rc + dt)
+        };
+        Filter {
+            high_pass,
+            alpha: alpha as f32,
+            previous_in: 0.0,
+            previous_out: 0.0,
+        }
+    }
+    fn apply(&mut self, sample: f32) -> f32 {
+        let out = if self.high_pass {
+            self.alpha * (self.previous_out + sample - self.previous_in)
+        } else {
+            self.previous_out + self.alpha * (sample - self.previous_out)
+        };
+        self.previous_in = sample;
+        self.previous_out = out;
+        out
+    }
+}
+
+/// turns the per cpu cycle output of the apu into samples at the sink's rate.
+/// the level only changes now and then, so each change is added as a band limited step
+/// spread over a few output samples, which keeps the tones above the output's nyquist
+/// frequency from aliasing back down
+pub struct AudioPipeline {
+    /// cpu cycles per second
+    clock_rate: f64,
+    sample_rate: u32,
+    channels: usize,
+    /// stretches the output, above 1 makes more samples
+    speed: f64,
+    /// output samples per cpu cycle
+    step: f64,
+    /// the current position in output samples, from the start of buffer
+    time: f64,
+    /// the level on the last cycle
+    level: f32,
+    /// the band limited impulse for each phase, it sums to 1
+    kernel: Vec<[f32; KERNEL_TAPS]>,
+    /// level changes spread out over output samples, summed up as they are read out
+    buffer: Vec<f32>,
+    sum: f32,
+    /// the console's high pass filters at 90Hz and 440Hz and low pass at 14kHz
+    filters: [Filter; 3],
+    /// the finished samples of a frame, interleaved
+    samples: Vec<f32>,
+    /// a pipeline for each channel on its own, made when stems are first added
+    stems: Vec<AudioPipeline>,
+}
+
+impl Default for AudioPipeline {
+    fn default() -> AudioPipeline {
+        AudioPipeline::new(Region::Ntsc.cpu_clock(), 48000, 1)
+    }
+}
+
+impl AudioPipeline {
+    pub fn new(clock_rate: f64, sample_rate: u32, channels: usize) -> AudioPipeline {
+        let mut kernel = vec![[0.0; KERNEL_TAPS]; KERNEL_PHASES + 1];
+        for (phase, taps) in kernel.iter_mut().enumerate() {
+            let fraction = phase as f64 / KERNEL_PHASES as f64;
+            let mut values = [0.0f64; KERNEL_TAPS];
+            for (tap, value) in values.iter_mut().enumerate() {
+                // distance from the step, which sits in the middle of the taps
+                let x = tap as f64 - (KERNEL_TAPS / 2) as f64 + 1.0 - fraction;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    let angle = std::f64::consts::PI * 2.0 * CUTOFF * x;
+                    angle.sin() / angle
+                };
+                // a blackman window over the taps
+                let position = (x + (KERNEL_TAPS / 2) as f64) / KERNEL_TAPS as f64;
+                let angle = 2.0 * std::f64::consts::PI * position;
+                let window = 0.42 - 0.5 * angle.cos() + 0.08 * (2.0 * angle).cos();
+                *value = sinc * window.max(0.0);
+            }
+            let total: f64 = values.iter().sum();
+            for (tap, value) in taps.iter_mut().zip(values.iter()) {
+                *tap = (value / total) as f32;
+            }
+        }
+        let mut pipeline = AudioPipeline {
+            clock_rate,
+            sample_rate,
+            channels: channels.max(1),
+            speed: 1.0,
+            step: 0.0,
+            time: 0.0,
+            level: 0.0,
+            kernel,
+            buffer: vec![0.0; KERNEL_TAPS],
+            sum: 0.0,
+            filters: [
+                Filter::new(true, 90.0, sample_rate),
+                Filter::new(true, 440.0, sample_rate),
+                Filter::new(false, 14000.0, sample_rate),
+            ],
+            samples: Vec::new(),
+            stems: Vec::new(),
+        };
+        pipeline.update_step();
+        pipeline
+    }
+    fn update_step(&mut self) {
+        self.step = self.sample_rate as f64 * self.speed / self.clock_rate;
+    }
+    /// changes the cpu clock, for when the region changes
+    pub fn set_clock_rate(&mut self, clock_rate: f64) {
+        self.clock_rate = clock_rate;
+        self.update_step();
+        for stem in self.stems.iter_mut() {
+            stem.set_clock_rate(clock_rate);
+        }
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+    /// stretches the output by a little, e.g. 1.001 makes a tenth of a percent more samples
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+        self.update_step();
+    }
+    /// the apu's output for one cpu cycle
+    pub fn add_sample(&mut self, level: f32) {
+        let delta = level - self.level;
+        if delta != 0.0 {
+            self.level = level;
+            let index = self.time as usize;
+            let fraction = self.time - index as f64;
+            let phase = (fraction * KERNEL_PHASES as f64).round() as usize;
+            if self.buffer.len() < index + KERNEL_TAPS {
+                self.buffer.resize(index + KERNEL_TAPS, 0.0);
+            }
+            for (out, tap) in self.buffer[index..]
+                .iter_mut()
+                .zip(self.kernel[phase].iter())
+            {
+                *out += delta * tap;
+            }
+        }
+        self.time += self.step;
+    }
+    /// each channel's output alone for one cpu cycle, see Mixer::mix_alone
+    pub fn add_stems(&mut self, levels: [f32; 5]) {
+        if self.stems.is_empty() {
+            for _ in Channel::ALL {
+                let mut stem = AudioPipeline::new(self.clock_rate, self.sample_rate, self.channels);
+                stem.set_speed(self.speed);
+                self.stems.push(stem);
+            }
+        }
+        for (stem, level) in self.stems.iter_mut().zip(levels) {
+            stem.add_sample(level);
+        }
+    }
+    /// hands the samples finished so far to the sink, and those of the stems if it wants them,
+    /// then adjusts the speed to its fill level
+    pub fn end_frame(&mut self, sink: &mut dyn AudioSink) {
+        self.finish_samples();
+        sink.samples(&self.samples);
+        for (channel, stem) in Channel::ALL.into_iter().zip(self.stems.iter_mut()) {
+            stem.finish_samples();
+            sink.stem(channel, &stem.samples);
+        }
+        if let Some(fill) = sink.fill_level() {
+            let fill = fill.clamp(0.0, 1.0) as f64;
+            self.set_speed(1.0 + (0.5 - fill) * 2.0 * MAX_RATE_DELTA);
+            for stem in self.stems.iter_mut() {
+                stem.set_speed(self.speed);
+            }
+        }
+    }
+    /// resamples and filters what is finished into samples
+    fn finish_samples(&mut self) {
+        // later changes only touch samples from the current one on
+        let finished = self.time as usize;
+        if self.buffer.len() < finished + KERNEL_TAPS {
+            self.buffer.resize(finished + KERNEL_TAPS, 0.0);
+        }
+        self.samples.clear();
+        for value in self.buffer.drain(..finished) {
+            self.sum += value;
+            let mut sample = self.sum;
+            for filter in self.filters.iter_mut() {
+                sample = filter.apply(sample);
+            }
+            for _ in 0..self.channels {
+                self.samples.push(sample);
+            }
+        }
+        self.time -= finished as f64;
+    }
+}
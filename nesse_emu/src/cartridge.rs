@@ -1,6 +1,6 @@
 use crate::{prelude::*, mapper::Mapper};
 
-/// a cartridge as described by a iNes 1.0 or 2.0 file (2.0 currently unimplemented)
+/// a cartridge as described by a iNes 1.0 or 2.0 file (of 2.0, only the timing and ram sizes are read)
 pub struct NesCart {
     pub header: NesCartHeader,
     trainer: Option<Vec<u8>>,
@@ -8,15 +8,52 @@ pub struct NesCart {
     prg_rom: Vec<u8>,
     /// character memory / pattern memory
     chr_rom: Vec<u8>,
+    /// carts without chr rom have 8kB of ram in its place
+    chr_is_ram: bool,
     prg_ram: Vec<u8>,
+    /// the second 2kB of nametable ram on four screen cartridges
+    nametable_ram: Vec<u8>,
     mapper: Box<dyn Mapper>,
 }
 
+/// how the 2kB of nametable ram is arranged in the four nametables at $2000-$2FFF
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mirroring {
+    /// $2000 = $2400 and $2800 = $2C00, for games that scroll vertically
+    Horizontal,
+    /// $2000 = $2800 and $2400 = $2C00, for games that scroll horizontally
+    Vertical,
+    /// all four are the first 1kB of ram
+    SingleScreenLower,
+    /// all four are the second 1kB of ram
+    SingleScreenUpper,
+    /// each is its own, with 2kB of extra ram on the cartridge
+    FourScreen,
+}
+
+impl Mirroring {
+    /// the offset into nametable ram of an address in $2000-$2FFF
+    pub fn nametable_offset(self, address: u16) -> usize {
+        let table = (address >> 10) & 0b11;
+        let offset = address & 0x3ff;
+        let bank = match self {
+            Mirroring::Horizontal => table >> 1,
+            Mirroring::Vertical => table & 1,
+            Mirroring::SingleScreenLower => 0,
+            Mirroring::SingleScreenUpper => 1,
+            Mirroring::FourScreen => table,
+        };
+        (bank * 0x400 + offset) as usize
+    }
+}
+
 pub struct NesCartHeader {
     mapper_id: u8,
     mirroring: u8,
     four_screen: bool,
     battery: bool,
+    /// the cpu/ppu timing bits of a NES 2.0 header, None for iNes 1.0
+    timing: Option<u8>,
 }
 
 impl NesCart {
@@ -37,11 +74,14 @@ impl NesCart {
         let vrom_count = header[5];
         let control_bytes: [u8; 2] = [header[6], header[7]];
         let ram_count = header[8];
+        let ram_shifts = header[10];
         let _reserved = header[9];
         let reserved_zeros: [u8; 6] = [
             header[10], header[11], header[12], header[13], header[14], header[15],
         ];
-        if reserved_zeros != [0, 0, 0, 0, 0, 0] {
+        // NES 2.0 sets bit 3 and clears bit 2 of byte 7, and uses the rest of the header
+        let nes2 = header[7] & 0b1100 == 0b1000;
+        if !nes2 && reserved_zeros != [0, 0, 0, 0, 0, 0] {
             println!("unexpected values reserved area of rom header:");
             println!(
                 "{} {} {} {} {} {}",
@@ -84,7 +124,13 @@ impl NesCart {
         // println!("number of 8kB ram banks: {}", ram_count);
         // println!("other byte: {:x}", reserved);
 
-        let unhandled_bits = FLAG_RESERVED_0 | FLAG_RESERVED_1 | FLAG_RESERVED_2 | FLAG_RESERVED_3;
+        let timing = if nes2 { Some(header[12] & 0b11) } else { None };
+
+        let unhandled_bits = if nes2 {
+            FLAG_RESERVED_0 | FLAG_RESERVED_1
+        } else {
+            FLAG_RESERVED_0 | FLAG_RESERVED_1 | FLAG_RESERVED_2 | FLAG_RESERVED_3
+        };
         let unhandled = control_bytes[1] & unhandled_bits;
 
         if unhandled != 0 {
@@ -99,6 +145,7 @@ impl NesCart {
             mirroring,
             four_screen,
             battery,
+            timing,
         };
 
         let trainer = if has_trainer {
@@ -122,14 +169,27 @@ impl NesCart {
         let chr_rom_size = vrom_count as usize * 8 * 1024;
         let mut chr_rom = vec![0u8; chr_rom_size];
         bytes.read_exact(&mut chr_rom).unwrap();
+        let chr_is_ram = vrom_count == 0;
+        if chr_is_ram {
+            chr_rom = vec![0u8; 8 * 1024];
+        }
         // println!("retreived {} bytes for chr_rom", chr_rom.len());
         // println!("  -chr_rom = {} bytes", bytes.len());
 
-        let prg_ram = if battery {
-            // todo: do we load in battery-backed ram from another file?
-            unimplemented!()
+        // todo: do we load in battery-backed ram from another file?
+        // a count of zero means 8kB, for compatibility with older files.
+        // NES 2.0 gives the size as a shift count instead, for both plain and battery backed ram
+        let prg_ram_size = if nes2 {
+            let shift_size = |shift: u8| if shift == 0 { 0 } else { 64usize << shift };
+            shift_size(ram_shifts & 0x0f) + shift_size(ram_shifts >> 4)
         } else {
-            Vec::with_capacity(8 * 1024 * ram_count as usize)
+            8 * 1024 * ram_count.max(1) as usize
+        };
+        let prg_ram = vec![0u8; prg_ram_size.max(8 * 1024)];
+        let nametable_ram = if four_screen {
+            vec![0u8; 2 * 1024]
+        } else {
+            vec![]
         };
 
         let mapper = Box::new(());
@@ -139,12 +199,50 @@ impl NesCart {
             trainer,
             prg_rom,
             chr_rom,
+            chr_is_ram,
             prg_ram,
+            nametable_ram,
             mapper,
         })
     }
 }
 
+impl NesCart {
+    /// the nametable arrangement: four screen if the cartridge has the ram for it,
+    /// otherwise whatever the mapper chooses, otherwise the solder pad in the header
+    pub fn mirroring(&self) -> Mirroring {
+        if self.header.four_screen {
+            Mirroring::FourScreen
+        } else if let Some(mirroring) = self.mapper.mirroring() {
+            mirroring
+        } else if self.header.mirroring == 0 {
+            Mirroring::Horizontal
+        } else {
+            Mirroring::Vertical
+        }
+    }
+    /// the region from a NES 2.0 header, None for older files that don't say
+    pub fn region(&self) -> Option<Region> {
+        self.header.timing.map(Region::from_nes2_timing)
+    }
+    /// reads the pattern tables, at $0000-$1FFF on the ppu bus
+    pub fn get_chr(&mut self, address: u16) -> u8 {
+        self.chr_rom.get(address as usize).copied().unwrap_or(0)
+    }
+    /// writes the pattern tables, if they are ram
+    pub fn set_chr(&mut self, address: u16, value: u8) {
+        if self.chr_is_ram {
+            if let Some(byte) = self.chr_rom.get_mut(address as usize) {
+                *byte = value;
+            }
+        }
+    }
+    /// the extra nametable ram of a four screen cartridge, empty otherwise
+    pub fn nametable_ram(&mut self) -> &mut [u8] {
+        &mut self.nametable_ram
+    }
+}
+
 impl Bus for NesCart {
     fn bounds(&self) -> (u16, u16) {
         (0x4020, 0xffff)
@@ -157,7 +255,8 @@ impl Bus for NesCart {
             unimplemented!()
         } else if address < 0x8000 {
             // optional ram, for e.g. zelda
-            unimplemented!()
+            let ram_address = (address - 0x6000) as usize % self.prg_ram.len();
+            self.prg_ram[ram_address] = value;
         } else {
             // cartridge rom
             // todo: does any cartridge even try?
@@ -175,8 +274,8 @@ impl Bus for NesCart {
             unimplemented!()
         } else if address < 0x8000 {
             // optional ram, for e.g. zelda
-            println!("tried getting option ram address {:04X}", address);
-            unimplemented!()
+            let ram_address = (address - 0x6000) as usize % self.prg_ram.len();
+            self.prg_ram[ram_address]
         } else {
             // cartridge rom
             let mut rom_address = address - 0x8000;
@@ -0,0 +1,169 @@
+//! the controller ports at $4016 and $4017.
+//! bit 0 of a $4016 write is the strobe: while it is high the controllers keep loading their
+//! buttons, and once it drops each read of a port shifts the next button out on bit 0
+
+/// reads the first controller port, writes strobe the controllers
+pub const CONTROLLER_PORT_1: u16 = 0x4016;
+/// reads the second controller port, writes go to the apu's frame counter
+pub const CONTROLLER_PORT_2: u16 = 0x4017;
+
+/// which buttons are held on a standard controller
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ButtonState {
+    pub a: bool,
+    pub b: bool,
+    pub select: bool,
+    pub start: bool,
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl ButtonState {
+    /// the buttons in the order the controller sends them, a in bit 0 to right in bit 7
+    pub fn bits(&self) -> u8 {
+        [
+            self.a,
+            self.b,
+            self.select,
+            self.start,
+            self.up,
+            self.down,
+            self.left,
+            self.right,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (bit, held)| bits | (*held as u8) << bit)
+    }
+    pub fn from_bits(bits: u8) -> ButtonState {
+        let held = |bit: u8| bits & (1 << bit) > 0;
+        ButtonState {
+            a: held(0),
+            b: held(1),
+            select: held(2),
+            start: held(3),
+            up: held(4),
+            down: held(5),
+            left: held(6),
+            right: held(7),
+        }
+    }
+}
+
+/// a standard controller, a latch for the buttons and an 8 bit shift register
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NesGamepad {
+    buttons: ButtonState,
+    shift_register: u8,
+    strobe: bool,
+}
+
+impl NesGamepad {
+    pub fn buttons(&self) -> ButtonState {
+        self.buttons
+    }
+    pub fn set_buttons(&mut self, buttons: ButtonState) {
+        self.buttons = buttons;
+        if self.strobe {
+            self.shift_register = buttons.bits();
+        }
+    }
+    /// bit 0 of a $4016 write, the buttons are latched while it is high
+    pub fn strobe(&mut self, high: bool) {
+        self.strobe = high;
+        if high {
+            self.shift_register = self.buttons.bits();
+        }
+    }
+    /// the next button, 1 if held. while the strobe is high this is always a,
+    /// and after all 8 buttons an official controller reads 1
+    pub fn read(&mut self) -> u8 {
+        if self.strobe {
+            return self.buttons.bits() & 1;
+        }
+        let bit = self.shift_register & 1;
+        self.shift_register = (self.shift_register >> 1) | 0x80;
+        bit
+    }
+}
+
+/// the four player adapters, each sends two controllers on a port one after the other
+/// and then a signature byte, so games can tell it is plugged in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdapterKind {
+    /// the nes four score, in both controller ports. players 1 and 3 are read on bit 0
+    /// of $4016, players 2 and 4 on bit 0 of $4017
+    FourScore,
+    /// the hori 4 players adapter in 4 player mode, in the famicom's expansion port.
+    /// the players are read on bit 1 instead, with the four score's signatures swapped.
+    /// the famicom's own controllers on bit 0 are not emulated
+    Hori,
+}
+
+impl AdapterKind {
+    /// the byte sent after both controllers on a port, lowest bit first like the buttons.
+    /// the four score reads 0,0,0,1,0,0,0,0 on $4016 and 0,0,1,0,0,0,0,0 on $4017
+    fn signature(&self, port: usize) -> u8 {
+        match (self, port) {
+            (AdapterKind::FourScore, 0) | (AdapterKind::Hori, 1) => 0x08,
+            _ => 0x04,
+        }
+    }
+    /// which bit of $4016 and $4017 the adapter drives
+    fn data_bit(&self) -> u8 {
+        match self {
+            AdapterKind::FourScore => 0,
+            AdapterKind::Hori => 1,
+        }
+    }
+}
+
+/// a four player adapter, with a 24 bit shift register for each port
+#[derive(Clone, Copy, Debug)]
+pub struct FourPlayerAdapter {
+    kind: AdapterKind,
+    shift_registers: [u32; 2],
+    strobe: bool,
+}
+
+impl FourPlayerAdapter {
+    pub fn new(kind: AdapterKind) -> FourPlayerAdapter {
+        FourPlayerAdapter {
+            kind,
+            shift_registers: [0; 2],
+            strobe: false,
+        }
+    }
+    pub fn kind(&self) -> AdapterKind {
+        self.kind
+    }
+    /// bit 0 of a $4016 write, the players' buttons are latched while it is high
+    pub fn strobe(&mut self, high: bool, gamepads: &[Option<NesGamepad>]) {
+        self.strobe = high;
+        if high {
+            self.load(gamepads);
+        }
+    }
+    /// the next bit of a port, already moved to the bit the adapter drives.
+    /// after the signature it reads 1, like a standard controller
+    pub fn read(&mut self, port: usize, gamepads: &[Option<NesGamepad>]) -> u8 {
+        if self.strobe {
+            self.load(gamepads);
+        }
+        let shift_register = &mut self.shift_registers[port];
+        let bit = (*shift_register & 1) as u8;
+        *shift_register = (*shift_register >> 1) | 1 << 23;
+        bit << self.kind.data_bit()
+    }
+    /// port 0 gets players 1 and 3, port 1 players 2 and 4
+    fn load(&mut self, gamepads: &[Option<NesGamepad>]) {
+        let buttons =
+            |player: usize| gamepads[player].map_or(0, |gamepad| gamepad.buttons().bits()) as u32;
+        for port in 0..2 {
+            let signature = self.kind.signature(port) as u32;
+            self.shift_registers[port] = buttons(port) | buttons(port + 2) << 8 | signature << 16;
+        }
+    }
+}
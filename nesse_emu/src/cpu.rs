@@ -1,4 +1,8 @@
+use crate::opcodes::jumptable::{Jumptable, Opcode};
+use crate::Bus;
+use crate::INITIAL_PC_LOCATION;
 use crate::STACK_INITIAL;
+use crate::STACK_OFFSET;
 use crate::STATUS_INITIAL;
 /// NES cpu instance
 #[derive(Default, Clone)]
@@ -9,10 +13,37 @@ pub struct Nes2a03 {
     pub cycles: u64,
     /// counter for when to process next instruction
     pub next_tick: u64,
+    /// cycles the cpu is held off the bus by dma, added once the current instruction finishes
+    pub stall: u64,
     /// small counter for dividing the master clock ticks without using division
     pub clock_counter: u8,
     /// the current cpu state
     pub registers: NesRegisters,
+    /// which instruction set to execute
+    pub variant: Variant,
+}
+
+/// the 6502 derivatives we can emulate
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variant {
+    /// the ricoh 2a03 in the nes, a nmos 6502 with decimal mode disconnected
+    #[default]
+    Ricoh2a03,
+    /// the original nmos 6502, with decimal adc and sbc
+    Nmos6502,
+    /// the wdc 65c02: new opcodes, fixed jmp indirect, and valid flags in decimal mode
+    Wdc65c02,
+}
+
+impl Variant {
+    /// the opcode jumptable generated for this variant
+    pub fn jumptable<'t, P: Processor + 't>(self) -> &'t [Opcode<P>; 256] {
+        match self {
+            Variant::Ricoh2a03 => &Jumptable::<P>::RICOH2A03,
+            Variant::Nmos6502 => &Jumptable::<P>::NMOS6502,
+            Variant::Wdc65c02 => &Jumptable::<P>::WDC65C02,
+        }
+    }
 }
 
 impl Nes2a03 {
@@ -55,6 +86,12 @@ pub trait RegisterAccess {
     fn status_carry(&self) -> bool {
         self.get_p() & FLAG_CARRY == FLAG_CARRY
     }
+    fn status_overflow(&self) -> bool {
+        self.get_p() & FLAG_OVERFLOW == FLAG_OVERFLOW
+    }
+    fn status_decimal(&self) -> bool {
+        self.get_p() & FLAG_DECIMAL == FLAG_DECIMAL
+    }
     fn get_status_stack(&self) -> u8 {
         let mut value = self.get_p();
         value |= FLAG_BL | FLAG_BH;
@@ -123,9 +160,19 @@ pub trait RegisterAccess {
     fn clear_decimal(&mut self) {
         self.set_p(self.get_p() & !FLAG_DECIMAL);
     }
+    fn set_interrupt(&mut self) {
+        self.set_p(self.get_p() | FLAG_INTERRUPT);
+    }
+    fn clear_interrupt(&mut self) {
+        self.set_p(self.get_p() & !FLAG_INTERRUPT);
+    }
     fn get_carry(&self) -> u8 {
         self.get_p() & FLAG_CARRY
     }
+    /// whether the i flag is masking irqs
+    fn interrupts_disabled(&self) -> bool {
+        self.get_p() & FLAG_INTERRUPT > 0
+    }
     fn increment_a(&mut self) {
         self.set_a(self.get_a().wrapping_add(1));
     }
@@ -338,3 +385,275 @@ impl RegisterAccess for Nes2a03 {
         old
     }
 }
+
+/// a 6502 core that instructions can run against: registers plus a bus.
+/// the opcode implementations are generic over this trait, so the same
+/// instructions drive the nes and any other 6502 machine.
+pub trait Processor: Bus + RegisterAccess {
+    /// stop running, e.g. on brk or a jammed opcode
+    fn halt(&mut self);
+
+    // provided methods
+    /// reads the operand of the current instruction and returns the address it refers to,
+    /// advancing pc past the operand bytes
+    fn address_from_mode(&mut self, mode: u8) -> u16 {
+        match mode {
+            0 => {
+                // Implicit
+                panic!("Should not request a get with implicit address mode.");
+            }
+            1 => {
+                // Accumulator
+                panic!("Should not request a get with accumulator address mode.");
+            }
+            2 => {
+                // Immediate
+                let value = self.get_pc();
+                self.increment_pc();
+                value
+            }
+            3 => {
+                // ZeroPage
+                let value = self.get(self.get_pc()) as u16;
+                self.increment_pc();
+                value
+            }
+            4 => {
+                // ZeroPageX
+                let base = self.get(self.get_pc());
+                self.increment_pc();
+                // todo: is this behavior correct? will wrap around zero page
+                base.wrapping_add(self.get_x()) as u16
+            }
+            5 => {
+                // ZeroPageY
+                let base = self.get(self.get_pc());
+                self.increment_pc();
+                // todo: is this wrap intended before the cast to u16 or after
+                base.wrapping_add(self.get_y()) as u16
+            }
+            6 => {
+                // Relative
+                let base = self.get(self.get_pc());
+                self.increment_pc();
+                base as u16
+            }
+            7 => {
+                // Absolute
+                let value = self.get_pc();
+                self.set_pc(value.wrapping_add(2)); // skips two bytes since pointers are a two byte value
+                self.get_short(value)
+            }
+            8 => {
+                // AbsoluteX
+                let address = self.get_short(self.get_pc());
+                self.set_pc(self.get_pc().wrapping_add(2));
+                address.wrapping_add(self.get_x() as u16)
+            }
+            9 => {
+                // AbsoluteY
+                let address = self.get_short(self.get_pc());
+                self.set_pc(self.get_pc().wrapping_add(2));
+                address.wrapping_add(self.get_y() as u16)
+            }
+            10 => {
+                // Indirect
+                let address = self.get_short(self.get_pc());
+                self.set_pc(self.get_pc().wrapping_add(2));
+                let address_lo = (address & 0xff) as u8;
+                let address_hi = address & 0xff00;
+                let lo = self.get(address) as u16;
+                let hi_address = address_lo.wrapping_add(1) as u16 | address_hi;
+                let hi = self.get(hi_address) as u16;
+                (hi << 8) | lo
+            }
+            11 => {
+                // IndexedIndirect (x)
+                // The address of the table is taken from the instruction and the X register added to it (with zero page wrap around) to give the location of the least significant byte of the target address.
+                let table = self.get(self.get_pc());
+                self.increment_pc();
+                let base = table.wrapping_add(self.get_x());
+                let lo = self.get(base as u16) as u16;
+                let hi = self.get(base.wrapping_add(1) as u16) as u16;
+                (hi << 8) | lo
+            }
+            12 => {
+                // IndirectIndexed (y)
+                let immediate = self.get(self.get_pc());
+                self.increment_pc();
+                let lo = self.get(immediate as u16) as u16;
+                let hi = self.get(immediate.wrapping_add(1) as u16) as u16;
+                let short = hi << 8 | lo;
+                short.wrapping_add(self.get_y() as u16)
+            }
+            13 => {
+                // ZeroPageIndirect, 65c02 only
+                let pointer = self.get(self.get_pc());
+                self.increment_pc();
+                let lo = self.get(pointer as u16) as u16;
+                let hi = self.get(pointer.wrapping_add(1) as u16) as u16;
+                (hi << 8) | lo
+            }
+            14 => {
+                // AbsoluteIndexedIndirect, 65c02 only
+                let base = self.get_short(self.get_pc());
+                self.set_pc(self.get_pc().wrapping_add(2));
+                let pointer = base.wrapping_add(self.get_x() as u16);
+                let lo = self.get(pointer) as u16;
+                let hi = self.get(pointer.wrapping_add(1)) as u16;
+                (hi << 8) | lo
+            }
+            _ => {
+                unimplemented!()
+            }
+        }
+    }
+    fn stack_push(&mut self, value: u8) {
+        let sp = self.get_sp();
+        self.set(sp as u16 + STACK_OFFSET, value);
+        self.set_sp(sp.wrapping_sub(1));
+    }
+    fn stack_pop(&mut self) -> u8 {
+        let sp = self.get_sp().wrapping_add(1);
+        let value = self.get(sp as u16 + STACK_OFFSET);
+        self.set_sp(sp);
+        value
+    }
+    fn stack_push_short(&mut self, value: u16) {
+        let lo = (value & 0xff) as u8;
+        let hi = ((value >> 8) & 0xff) as u8;
+        self.stack_push(hi);
+        self.stack_push(lo);
+    }
+    fn stack_pop_short(&mut self) -> u16 {
+        let lo = self.stack_pop() as u16;
+        let hi = self.stack_pop() as u16;
+        (hi << 8) | lo
+    }
+    /// pushes pc and p with the b flag clear, then jumps through the vector, as nmi and irq do
+    fn interrupt(&mut self, vector: u16) {
+        let pc = self.get_pc();
+        self.stack_push_short(pc);
+        let status = (self.get_p() | FLAG_BH) & !FLAG_BL;
+        self.stack_push(status);
+        self.set_interrupt();
+        let target = self.get_short(vector);
+        self.set_pc(target);
+    }
+}
+
+/// a bare 6502 wired to an arbitrary bus, e.g. a flat 64KB ram for running test programs
+#[derive(Default)]
+pub struct Cpu6502<B: Bus> {
+    /// the cpu state, shared with the nes
+    pub cpu: Nes2a03,
+    /// everything the cpu can read and write
+    pub bus: B,
+}
+
+impl<B: Bus> Cpu6502<B> {
+    pub fn new(bus: B) -> Cpu6502<B> {
+        Cpu6502 {
+            cpu: Nes2a03::default(),
+            bus,
+        }
+    }
+    pub fn with_variant(mut self, variant: Variant) -> Cpu6502<B> {
+        self.cpu.variant = variant;
+        self
+    }
+    /// resets registers and loads pc from the reset vector
+    pub fn init(&mut self) {
+        self.cpu.registers.reset();
+        self.cpu.cycles = 7;
+        let initial_pc = self.get_short(INITIAL_PC_LOCATION);
+        self.cpu.registers.pc = initial_pc;
+    }
+    /// runs a single instruction, returning the cycles it took
+    pub fn step(&mut self) -> u8 {
+        let opcode = self.get(self.cpu.registers.pc);
+        self.cpu.registers.pc = self.cpu.registers.pc.wrapping_add(1);
+        let instruction = unsafe {
+            // SAFETY: this is safe because we generate the jumptable
+            // with 256 entries, which covers all possible u8 indexes
+            self.cpu
+                .variant
+                .jumptable::<Self>()
+                .get_unchecked(opcode as usize)
+        };
+        let cycles_spent = instruction.run(self);
+        self.cpu.cycles += cycles_spent as u64;
+        cycles_spent
+    }
+    /// sets the cpu to running and steps until halted
+    pub fn run(&mut self) {
+        self.cpu.running = true;
+        while self.cpu.running {
+            self.step();
+        }
+    }
+    pub fn dump_registers(&self) -> NesRegisters {
+        self.cpu.registers.clone()
+    }
+    pub fn inject_registers(&mut self, regs: NesRegisters) {
+        self.cpu.registers = regs;
+    }
+}
+
+impl<B: Bus> Bus for Cpu6502<B> {
+    fn bounds(&self) -> (u16, u16) {
+        self.bus.bounds()
+    }
+    fn set(&mut self, address: u16, value: u8) {
+        self.bus.set(address, value);
+    }
+    fn get(&mut self, address: u16) -> u8 {
+        self.bus.get(address)
+    }
+}
+
+impl<B: Bus> RegisterAccess for Cpu6502<B> {
+    fn get_a(&self) -> u8 {
+        self.cpu.get_a()
+    }
+    fn get_x(&self) -> u8 {
+        self.cpu.get_x()
+    }
+    fn get_y(&self) -> u8 {
+        self.cpu.get_y()
+    }
+    fn get_p(&self) -> u8 {
+        self.cpu.get_p()
+    }
+    fn get_sp(&self) -> u8 {
+        self.cpu.get_sp()
+    }
+    fn get_pc(&self) -> u16 {
+        self.cpu.get_pc()
+    }
+
+    fn set_a(&mut self, value: u8) -> u8 {
+        self.cpu.set_a(value)
+    }
+    fn set_x(&mut self, value: u8) -> u8 {
+        self.cpu.set_x(value)
+    }
+    fn set_y(&mut self, value: u8) -> u8 {
+        self.cpu.set_y(value)
+    }
+    fn set_p(&mut self, value: u8) -> u8 {
+        self.cpu.set_p(value)
+    }
+    fn set_sp(&mut self, value: u8) -> u8 {
+        self.cpu.set_sp(value)
+    }
+    fn set_pc(&mut self, value: u16) -> u16 {
+        self.cpu.set_pc(value)
+    }
+}
+
+impl<B: Bus> Processor for Cpu6502<B> {
+    fn halt(&mut self) {
+        self.cpu.running = false;
+    }
+}
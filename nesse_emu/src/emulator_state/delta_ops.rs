@@ -71,6 +71,23 @@ fn translate_address<N: AddressableMemory + RegisterAccess>(
             let hi = snap.get(base.wrapping_add(1) as u16);
             (hi as u16) << 8 | (lo as u16).wrapping_add(snap.get_y() as u16)
         }
+        ZeroPageIndirect => {
+            let base = snap.get(snap.get_pc());
+            snap.increment_pc();
+            let lo = snap.get(base as u16) as u16;
+            let hi = snap.get(base.wrapping_add(1) as u16) as u16;
+            (hi << 8) | lo
+        }
+        AbsoluteIndexedIndirect => {
+            let lo = snap.get(snap.get_pc()) as u16;
+            snap.increment_pc();
+            let hi = snap.get(snap.get_pc()) as u16;
+            snap.increment_pc();
+            let pointer = ((hi << 8) | lo).wrapping_add(snap.get_x() as u16);
+            let lo = snap.get(pointer) as u16;
+            let hi = snap.get(pointer.wrapping_add(1)) as u16;
+            (hi << 8) | lo
+        }
     }
 }
 
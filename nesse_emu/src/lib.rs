@@ -7,34 +7,62 @@ mod test;
 #[cfg(feature = "delta")]
 mod emulator_state;
 
+pub mod apu;
+pub mod audio;
 pub mod cartridge;
+pub mod controller;
 pub mod cpu;
 pub mod mapper;
+pub mod ntsc;
 mod opcodes;
+pub mod palette;
 pub mod peripherals;
 pub mod ppu;
+pub mod region;
+pub mod video;
 
 pub use opcodes::opcode_debug::OPCODE_NAMES;
 
 pub mod prelude {
     // todo: select useful items to include in prelude
-    pub use crate::cartridge::NesCart;
-    pub use crate::cpu::{NesRegisters, RegisterAccess};
+    pub use crate::apu::{Channel, ChannelState};
+    pub use crate::audio::{AudioPipeline, AudioSink};
+    pub use crate::cartridge::{Mirroring, NesCart};
+    pub use crate::controller::{AdapterKind, ButtonState, FourPlayerAdapter, NesGamepad};
+    pub use crate::cpu::{Cpu6502, NesRegisters, Processor, RegisterAccess, Variant};
+    pub use crate::ntsc::{NtscFilter, NtscSetup};
+    pub use crate::palette::{Palette, VideoFilter};
     pub use crate::peripherals::NesPeripheral;
-    pub use crate::ppu::Nes2c02;
+    pub use crate::ppu::{Nes2c02, OamEntry, RgbaImage};
+    pub use crate::region::Region;
+    pub use crate::video::{Frame, RgbFrameBuffer, VideoSink};
     pub use crate::*;
 }
 
-pub use opcodes::jumptable::OPCODE_JUMPTABLE;
+pub use apu::Nes2a03Audio;
+pub use controller::NesGamepad;
+pub use opcodes::jumptable::{Jumptable, Opcode};
 
+use crate::apu::APU_STATUS;
 use crate::cartridge::NesCart;
+use crate::controller::{AdapterKind, ButtonState, FourPlayerAdapter};
+use crate::controller::{CONTROLLER_PORT_1, CONTROLLER_PORT_2};
 use crate::cpu::Nes2a03;
-use crate::cpu::{NesRegisters, RegisterAccess};
-use crate::ppu::Nes2c02;
+use crate::cpu::{NesRegisters, Processor, RegisterAccess};
+use crate::ppu::{Nes2c02, FRAME_WIDTH, PPU_DATA, PPU_OAM_DATA, PPU_OAM_DMA};
+use crate::region::Region;
+use audio::{AudioPipeline, AudioSink};
 use peripherals::NesPeripheral;
+use video::VideoSink;
 
 // the value loaded into pc is stored in this location
 const INITIAL_PC_LOCATION: u16 = 0xfffc;
+// the nmi handler's address is stored in this location
+const NMI_VECTOR: u16 = 0xfffa;
+// the irq handler's address is stored in this location, shared with brk
+const IRQ_VECTOR: u16 = 0xfffe;
+// the cycles of brk, nmi and irq that push pc and p, as offsets from the first
+const INTERRUPT_WRITES: (u8, u8) = (2, 5);
 // A value added to the SP on every stack operation
 pub const STACK_OFFSET: u16 = 1 << 8;
 /// The value of the stack pointer on reset
@@ -50,9 +78,26 @@ pub struct Nes<'a> {
     pub ram: NesRam,
     pub apu: Nes2a03Audio,
     pub cartridge: Option<NesCart>,
+    /// the controllers, the first two are plugged into the ports at $4016 and $4017.
+    /// the third and fourth are heard through four_player_adapter, the rest are unused
     pub gamepads: [Option<NesGamepad>; 8],
+    /// a four score or hori adapter, which reads the first four gamepads in place of the ports
+    pub four_player_adapter: Option<FourPlayerAdapter>,
+    /// the last value on the cpu's data bus, which the unconnected bits of a read pick up
+    open_bus: u8,
+    /// the last address the cpu read, which it keeps reading while halted by dmc dma
+    last_read: u16,
+    /// the bus cycles of the instruction the cpu is running, to line dmc dma up with
+    instruction: InstructionCycles,
+    /// ntsc, pal or dendy timing
+    region: Region,
     // todo: switch to enum_dispatch
     pub peripherals: Option<Vec<&'a mut dyn NesPeripheral>>,
+    /// where finished frames and scanlines go
+    pub video: Option<&'a mut dyn VideoSink>,
+    /// where the sound goes, through audio_pipeline
+    pub audio: Option<&'a mut dyn AudioSink>,
+    pub audio_pipeline: AudioPipeline,
 }
 
 #[test]
@@ -64,30 +109,38 @@ fn nes_size() {
     // assert_eq!(mw, 4 );
     // assert_eq!(rw, 6 );
     // assert_eq!(gw, 1 );
-    assert_eq!(nes, 4592);
+    assert_eq!(nes, 6544);
 }
 
 impl<'a> Nes<'a> {
     /// a single tick of the master clock
-    /// ticks the cpu every 1 ticks and ticks the cpu every 12
-    /// also increments a frame counter and calls Nes::on_frame() every 29780 cpu ticks.
+    /// ticks the ppu every 4 ticks and ticks the cpu every 12 (5 and 16 on pal, 5 and 15 on dendy),
+    /// each at the start of its own clock period.
+    /// also calls Nes::on_frame() when the ppu enters vblank.
     pub fn master_tick(&mut self) {
-        self.ppu.clock_counter += 1;
-        if self.ppu.clock_counter >= 4 {
-            self.ppu.tick(&mut self.cartridge);
-            self.ppu.clock_counter -= 4;
-        }
-        self.cpu.clock_counter += 1;
-        if self.cpu.clock_counter >= 12 {
+        if self.cpu.clock_counter == 0 {
             self.step();
-            self.cpu.clock_counter -= 12;
-            self.ppu.frame_clock += 1;
-            if self.ppu.frame_clock >= 29780 {
-                // note: alternate +1 on odd frames
-                self.ppu.frame_clock -= 29780;
+        }
+        if self.ppu.clock_counter == 0 {
+            self.ppu.tick(&mut self.cartridge);
+            if let Some(y) = self.ppu.take_scanline() {
+                if let Some(video) = self.video.as_mut() {
+                    let line = &self.ppu.framebuffer()[y * FRAME_WIDTH..(y + 1) * FRAME_WIDTH];
+                    video.scanline(y, line);
+                }
+            }
+            if self.ppu.take_frame() {
                 self.on_frame();
             }
         }
+        self.cpu.clock_counter += 1;
+        if self.cpu.clock_counter >= self.region.cpu_divider() {
+            self.cpu.clock_counter = 0;
+        }
+        self.ppu.clock_counter += 1;
+        if self.ppu.clock_counter >= self.region.ppu_divider() {
+            self.ppu.clock_counter = 0;
+        }
     }
     /// sets the cpu to running and runs until stopped by some external force
     pub fn master_clock_drive(&mut self) {
@@ -96,8 +149,15 @@ impl<'a> Nes<'a> {
             self.master_tick();
         }
     }
-    /// calls on_vblank on every peripheral
+    /// hands the frame to the video sink and its sound to the audio sink,
+    /// then calls on_vblank on every peripheral
     pub fn on_frame(&mut self) {
+        if let Some(video) = self.video.as_mut() {
+            video.frame(&self.ppu.frame_output());
+        }
+        if let Some(audio) = self.audio.as_mut() {
+            self.audio_pipeline.end_frame(&mut **audio);
+        }
         if let Some(mut peripherals) = self.peripherals.take() {
             for p in peripherals.iter_mut() {
                 p.on_vblank(self);
@@ -108,6 +168,13 @@ impl<'a> Nes<'a> {
     pub fn init(&mut self) {
         self.cpu.registers.reset();
         self.cpu.cycles = 7; // todo: model startup
+
+        // the ppu keeps running during the reset sequence
+        let ppu_ticks =
+            self.cpu.cycles * self.region.cpu_divider() as u64 / self.region.ppu_divider() as u64;
+        for _ in 0..ppu_ticks {
+            self.ppu.tick(&mut self.cartridge);
+        }
         let initial_pc = self.get_short(INITIAL_PC_LOCATION);
         // println!("setting pc to {:x}", initial_pc);
         self.set_pc(initial_pc);
@@ -118,6 +185,29 @@ impl<'a> Nes<'a> {
             self.peripherals.replace(peripherals);
         }
     }
+    /// presses the reset button: pc is loaded from the reset vector, the stack pointer
+    /// moves down three bytes and interrupts are disabled. ram and other registers are kept
+    pub fn reset(&mut self) {
+        let sp = self.cpu.get_sp();
+        self.cpu.set_sp(sp.wrapping_sub(3));
+        self.cpu.set_interrupt();
+        let initial_pc = self.get_short(INITIAL_PC_LOCATION);
+        self.set_pc(initial_pc);
+        self.cpu.next_tick = self.cpu.cycles + 7;
+        // the reset line goes to the ppu and apu too
+        self.ppu.reset();
+        self.apu.reset();
+    }
+    /// copies a page of cpu memory into oam through $2004, halting the cpu for 513 cycles,
+    /// or 514 when it starts on an odd cycle
+    fn oam_dma(&mut self, page: u8) {
+        for offset in 0..=0xff {
+            let value = self.get(u16::from_be_bytes([page, offset]));
+            self.ppu
+                .write_register(&mut self.cartridge, PPU_OAM_DATA, value);
+        }
+        self.cpu.stall += 513 + (self.cpu.cycles & 1);
+    }
     pub fn cleanup(&mut self) {
         if let Some(mut peripherals) = self.peripherals.take() {
             for p in peripherals.iter_mut() {
@@ -140,6 +230,20 @@ impl<'a> Nes<'a> {
         self.set_region(address, memory);
         self
     }
+    pub fn with_video_sink(mut self, video: &'a mut dyn VideoSink) -> Nes<'a> {
+        self.video = Some(video);
+        self
+    }
+    /// sends sound to the sink, at its sample rate
+    pub fn with_audio_sink(mut self, audio: &'a mut dyn AudioSink) -> Nes<'a> {
+        self.audio_pipeline = AudioPipeline::new(
+            self.region.cpu_clock(),
+            audio.sample_rate(),
+            audio.channels(),
+        );
+        self.audio = Some(audio);
+        self
+    }
     pub fn with_peripheral(mut self, p: &'a mut dyn NesPeripheral) -> Nes<'a> {
         self.add_peripheral(p);
         self
@@ -184,132 +288,6 @@ impl<'a> Nes<'a> {
         }
         stack
     }
-    fn get_address_from_mode(&mut self, mode: u8) -> u16 {
-        match mode {
-            0 => {
-                // Implicit
-                panic!("Should not request a get with implicit address mode.");
-            }
-            1 => {
-                // Accumulator
-                panic!("Should not request a get with accumulator address mode.");
-            }
-            2 => {
-                // Immediate
-                let value = self.cpu.registers.pc;
-                self.cpu.registers.pc += 1;
-                value
-            }
-            3 => {
-                // ZeroPage
-                let value = self.get(self.cpu.registers.pc) as u16;
-                self.cpu.registers.pc += 1;
-                value
-            }
-            4 => {
-                // ZeroPageX
-                let base = self.get(self.cpu.registers.pc);
-                self.cpu.registers.pc += 1;
-                // todo: is this behavior correct? will wrap around zero page
-                base.wrapping_add(self.cpu.registers.x) as u16
-            }
-            5 => {
-                // ZeroPageY
-                let base = self.get(self.cpu.registers.pc);
-                self.cpu.registers.pc += 1;
-                // todo: is this wrap intended before the cast to u16 or after
-                base.wrapping_add(self.cpu.registers.y) as u16
-            }
-            6 => {
-                // Relative
-                let base = self.get(self.cpu.registers.pc);
-                self.cpu.registers.pc += 1;
-                base as u16
-            }
-            7 => {
-                // Absolute
-                let value = self.cpu.registers.pc;
-                self.cpu.registers.pc += 2; // skips two bytes since pointers are a two byte value
-                self.get_short(value)
-            }
-            8 => {
-                // AbsoluteX
-                let address = self.get_short(self.cpu.registers.pc);
-                self.cpu.registers.pc += 2;
-                address.wrapping_add(self.cpu.registers.x as u16)
-            }
-            9 => {
-                // AbsoluteY
-                let address = self.get_short(self.cpu.registers.pc);
-                self.cpu.registers.pc += 2;
-                address.wrapping_add(self.cpu.registers.y as u16)
-            }
-            10 => {
-                // Indirect
-                let address = self.get_short(self.cpu.registers.pc);
-                self.cpu.registers.pc += 2;
-                let address_lo = (address & 0xff) as u8;
-                let address_hi = address & 0xff00;
-                let lo = self.get(address) as u16;
-                let hi_address = address_lo.wrapping_add(1) as u16 | address_hi;
-                let hi = self.get(hi_address) as u16;
-                (hi << 8) | lo
-            }
-            11 => {
-                // IndexedIndirect (x)
-                // The address of the table is taken from the instruction and the X register added to it (with zero page wrap around) to give the location of the least significant byte of the target address.
-                let table = self.get(self.cpu.registers.pc);
-                self.cpu.registers.pc += 1;
-                let base = table.wrapping_add(self.cpu.registers.x);
-                let lo = self.get(base as u16) as u16;
-                let hi = self.get(base.wrapping_add(1) as u16) as u16;
-                (hi << 8) | lo
-            }
-            12 => {
-                // IndirectIndexed (y)
-                // let immediate = nes.get(pc + 1);
-                // let lo = nes.get(immediate as u16) as u16;
-                // let hi = nes.get(immediate.wrapping_add(1) as u16) as u16; // wraps around zero page
-                // let short = hi << 8 | lo;
-                // let address = short.wrapping_add(regs.y as u16);
-                // let value = nes.get(address);
-                let immediate = self.get(self.cpu.registers.pc);
-                self.cpu.registers.pc += 1;
-                let lo = self.get(immediate as u16) as u16;
-                let hi = self.get(immediate.wrapping_add(1) as u16) as u16;
-                let short = hi << 8 | lo;
-                short.wrapping_add(self.cpu.registers.y as u16)
-                // (hi as u16) << 8 | (lo as u16).wrapping_add(self.cpu.registers.y as u16)
-            }
-            _ => {
-                unimplemented!()
-            }
-        }
-    }
-    pub fn stack_push(&mut self, value: u8) {
-        let mut sp = self.cpu.registers.sp;
-        self.set(sp as u16 + STACK_OFFSET, value);
-        sp = sp.wrapping_sub(1);
-        self.cpu.registers.sp = sp;
-    }
-    pub fn stack_pop(&mut self) -> u8 {
-        let mut sp = self.cpu.registers.sp;
-        sp = sp.wrapping_add(1);
-        let value = self.get(sp as u16 + STACK_OFFSET);
-        self.cpu.registers.sp = sp;
-        value
-    }
-    pub fn stack_push_short(&mut self, value: u16) {
-        let lo = (value & 0xff) as u8;
-        let hi = ((value >> 8) & 0xff) as u8;
-        self.stack_push(hi);
-        self.stack_push(lo);
-    }
-    pub fn stack_pop_short(&mut self) -> u16 {
-        let lo = self.stack_pop() as u16;
-        let hi = self.stack_pop() as u16;
-        (hi << 8) | lo
-    }
     /// steps into one instruction.
     pub fn step(&mut self) {
         if self.cpu.next_tick <= self.cpu.cycles {
@@ -319,18 +297,88 @@ impl<'a> Nes<'a> {
                 }
                 self.peripherals.replace(peripherals);
             }
-            let opcode = self.peek_pc();
-            self.cpu.registers.pc += 1;
-            let instruction = unsafe {
-                // SAFETY: this is safe because we generate the jumptable
-                // with 256 entries, which covers all possible u8 indexes
-                OPCODE_JUMPTABLE.get_unchecked(opcode as usize)
+            let (cycles_spent, writes) = if self.ppu.take_nmi() {
+                self.interrupt(NMI_VECTOR);
+                (7, INTERRUPT_WRITES)
+            } else if self.apu.irq() && !self.interrupts_disabled() {
+                self.interrupt(IRQ_VECTOR);
+                (7, INTERRUPT_WRITES)
+            } else {
+                let opcode = self.peek_pc();
+                self.cpu.registers.pc += 1;
+                let instruction = unsafe {
+                    // SAFETY: this is safe because we generate the jumptable
+                    // with 256 entries, which covers all possible u8 indexes
+                    self.cpu
+                        .variant
+                        .jumptable::<Self>()
+                        .get_unchecked(opcode as usize)
+                };
+                let cycles_spent = instruction.run(self);
+                let writes = write_cycles(opcode, instruction.addressing, cycles_spent);
+                (cycles_spent, writes)
             };
-            let cycles_spent = instruction.run(self);
-            self.cpu.next_tick = self.cpu.cycles + cycles_spent as u64;
-        } else {
-            self.cpu.cycles += 1;
+            let stall = std::mem::take(&mut self.cpu.stall);
+            let end = self.cpu.cycles + cycles_spent as u64;
+            self.instruction = InstructionCycles {
+                start: self.cpu.cycles,
+                end,
+                dma_end: end + stall,
+                writes,
+            };
+            self.cpu.next_tick = end + stall;
+        }
+        self.cpu.cycles += 1;
+        self.apu.tick();
+        if let Some(audio) = self.audio.as_ref() {
+            self.audio_pipeline.add_sample(self.apu.output());
+            if audio.wants_stems() {
+                self.audio_pipeline.add_stems(self.apu.stem_outputs());
+            }
+        }
+        if let Some(address) = self.apu.dmc.dma_request() {
+            let stall = self.dmc_dma_stall();
+            let value = self.get(address);
+            self.apu.dmc.dma_fill(value);
+            self.cpu.next_tick += stall;
+        }
+    }
+    /// how many cycles the cpu is halted for while the dmc reads a sample byte,
+    /// starting on the cycle about to run.
+    /// the halt waits for a read cycle, then there is a dummy cycle and the read itself,
+    /// which has to land on a get cycle, so 3 or 4 cycles, fewer if the cpu was writing.
+    /// during oam dma the dmc read takes one of its get cycles, and oam dma lines up again after
+    fn dmc_dma_stall(&mut self) -> u64 {
+        let cycle = self.cpu.cycles;
+        let InstructionCycles {
+            start,
+            end,
+            dma_end,
+            writes,
+        } = self.instruction;
+        if cycle >= end && cycle < dma_end {
+            return 2;
+        }
+        let mut halt = cycle;
+        while halt < end && (writes.0..writes.1).contains(&((halt - start) as u8)) {
+            halt += 1;
         }
+        // get and put cycles alternate with the apu's clock
+        let apu_offset = self.apu.cycles.wrapping_sub(self.cpu.cycles);
+        let mut get = (cycle + 2).max(halt + 1);
+        if get.wrapping_add(apu_offset) & 1 == 1 {
+            get += 1;
+        }
+        // halted on an instruction's last read, the cpu reads the same address again
+        // until the dma is done. registers that change when read see the extra read:
+        // $2007 moves the vram address along and the controllers lose a bit
+        let address = self.last_read;
+        let ppu_data = (0x2000..0x4000).contains(&address) && address & 0b111 == PPU_DATA;
+        let controller = address == CONTROLLER_PORT_1 || address == CONTROLLER_PORT_2;
+        if halt + 1 == end && (ppu_data || controller) {
+            self.get(address);
+        }
+        get - halt + 1
     }
     /// returns a string with the registers
     pub fn display_registers(&self) -> String {
@@ -340,9 +388,59 @@ impl<'a> Nes<'a> {
     pub fn peek_pc(&mut self) -> u8 {
         self.get(self.cpu.registers.pc)
     }
-    /// inserts the cartridge
+    /// plugs a four player adapter in, for games with up to 4 players
+    pub fn with_four_player_adapter(mut self, kind: AdapterKind) -> Nes<'a> {
+        self.four_player_adapter = Some(FourPlayerAdapter::new(kind));
+        self
+    }
+    /// holds down buttons on player 0 to 3's controller, plugging one in if needed.
+    /// players 2 and 3 need a four player adapter, any other player is ignored
+    pub fn set_buttons(&mut self, player: usize, buttons: ButtonState) {
+        if player >= 4 {
+            return;
+        }
+        self.gamepads[player]
+            .get_or_insert_with(NesGamepad::default)
+            .set_buttons(buttons);
+    }
+    /// a write to bit 0 of $4016 strobes every controller
+    fn strobe_controllers(&mut self, value: u8) {
+        let high = value & 1 > 0;
+        for gamepad in self.gamepads.iter_mut().flatten() {
+            gamepad.strobe(high);
+        }
+        if let Some(adapter) = self.four_player_adapter.as_mut() {
+            adapter.strobe(high, &self.gamepads);
+        }
+    }
+    /// a read of $4016 or $4017: the controller's bit, with the top 3 bits left on the bus.
+    /// an empty port reads 0
+    fn read_controller(&mut self, address: u16) -> u8 {
+        let port = (address - CONTROLLER_PORT_1) as usize;
+        let bits = if let Some(adapter) = self.four_player_adapter.as_mut() {
+            adapter.read(port, &self.gamepads)
+        } else {
+            self.gamepads[port].as_mut().map_or(0, |pad| pad.read())
+        };
+        bits | (self.open_bus & 0b1110_0000)
+    }
+    /// the console timing, ntsc unless changed or set by a cartridge
+    pub fn console_region(&self) -> Region {
+        self.region
+    }
+    /// changes the console timing, usually before init
+    pub fn set_console_region(&mut self, region: Region) {
+        self.region = region;
+        self.ppu.region = region;
+        self.apu.region = region;
+        self.audio_pipeline.set_clock_rate(region.cpu_clock());
+    }
+    /// inserts a cartridge, switching to its region if the header gives one
     pub fn insert_cartridge(&mut self, cart: NesCart) {
         if self.cartridge.is_none() {
+            if let Some(region) = cart.region() {
+                self.set_console_region(region);
+            }
             self.cartridge = Some(cart);
         } else {
             //todo: do we need to unload the existing cart before discarding?
@@ -351,6 +449,52 @@ impl<'a> Nes<'a> {
     }
 }
 
+impl<'a> RegisterAccess for Nes<'a> {
+    fn get_a(&self) -> u8 {
+        self.cpu.get_a()
+    }
+    fn get_x(&self) -> u8 {
+        self.cpu.get_x()
+    }
+    fn get_y(&self) -> u8 {
+        self.cpu.get_y()
+    }
+    fn get_p(&self) -> u8 {
+        self.cpu.get_p()
+    }
+    fn get_sp(&self) -> u8 {
+        self.cpu.get_sp()
+    }
+    fn get_pc(&self) -> u16 {
+        self.cpu.get_pc()
+    }
+
+    fn set_a(&mut self, value: u8) -> u8 {
+        self.cpu.set_a(value)
+    }
+    fn set_x(&mut self, value: u8) -> u8 {
+        self.cpu.set_x(value)
+    }
+    fn set_y(&mut self, value: u8) -> u8 {
+        self.cpu.set_y(value)
+    }
+    fn set_p(&mut self, value: u8) -> u8 {
+        self.cpu.set_p(value)
+    }
+    fn set_sp(&mut self, value: u8) -> u8 {
+        self.cpu.set_sp(value)
+    }
+    fn set_pc(&mut self, value: u16) -> u16 {
+        self.cpu.set_pc(value)
+    }
+}
+
+impl<'a> Processor for Nes<'a> {
+    fn halt(&mut self) {
+        self.cpu.running = false;
+    }
+}
+
 impl<'a> Bus for Nes<'a> {
     fn bounds(&self) -> (u16, u16) {
         (0, 0xffff)
@@ -361,7 +505,11 @@ impl<'a> Bus for Nes<'a> {
             self.ram.set(address, value);
         } else if address < 0x4000 {
             // ppu access
-            self.ppu.set(address, value);
+            self.ppu.write_register(&mut self.cartridge, address, value);
+        } else if address == PPU_OAM_DMA {
+            self.oam_dma(value);
+        } else if address == CONTROLLER_PORT_1 {
+            self.strobe_controllers(value);
         } else if address < 0x4020 {
             // apu registers
             self.apu.set(address, value);
@@ -373,12 +521,16 @@ impl<'a> Bus for Nes<'a> {
         }
     }
     fn get(&mut self, address: u16) -> u8 {
-        if address < 0x2000 {
+        let value = if address < 0x2000 {
             // nes base ram
             self.ram.get(address)
         } else if address < 0x4000 {
             // ppu access
-            self.ppu.get(address)
+            self.ppu.read_register(&mut self.cartridge, address)
+        } else if address == CONTROLLER_PORT_1 || address == CONTROLLER_PORT_2 {
+            self.read_controller(address)
+        } else if address == APU_STATUS {
+            self.apu.status(self.open_bus)
         } else if address < 0x4020 {
             // apu registers
             self.apu.get(address)
@@ -389,7 +541,42 @@ impl<'a> Bus for Nes<'a> {
             } else {
                 0
             }
+        };
+        self.open_bus = value;
+        self.last_read = address;
+        value
+    }
+}
+
+/// when an instruction runs on the bus, in cpu cycles
+#[derive(Default, Clone, Copy)]
+struct InstructionCycles {
+    /// the cycle it started on
+    start: u64,
+    /// the cycle after its last one
+    end: u64,
+    /// the end of any oam dma that follows it
+    dma_end: u64,
+    /// the cycles that write, as a range of offsets from the start
+    writes: (u8, u8),
+}
+
+/// which cycles of an instruction write, as a range of offsets from its first cycle.
+/// the writes come at the end, apart from jsr and brk which read the new pc after pushing
+fn write_cycles(opcode: u8, addressing: u8, cycles: u8) -> (u8, u8) {
+    let (_, name) = OPCODE_NAMES[opcode as usize];
+    match name.trim_start_matches('*') {
+        "STA" | "STX" | "STY" | "SAX" | "PHA" | "PHP" => (cycles - 1, cycles),
+        // read modify write instructions write the old value back before the new one
+        "ASL" | "LSR" | "ROL" | "ROR" | "INC" | "DEC" | "SLO" | "RLA" | "SRE" | "RRA" | "DCP"
+        | "ISB"
+            if addressing != 1 =>
+        {
+            (cycles - 2, cycles)
         }
+        "JSR" => (3, 5),
+        "BRK" => INTERRUPT_WRITES,
+        _ => (0, 0),
     }
 }
 
@@ -449,62 +636,36 @@ impl Bus for NesRam {
     }
     fn get(&mut self, address: u16) -> u8 {
         if self.bounds_check(address) {
-            self.inner[address as usize]
+            let mirror = address & 0x7ff;
+            self.inner[mirror as usize]
         } else {
             0
         }
     }
 }
 
-#[derive(Default)]
-pub struct Nes2a03Audio {
-    /// apu register $4000,$4001,$4002,$4003,
-    // pulse_1: u32,
-    /// apu register $4004,$4005,$4006,$4007,
-    // pulse_2: u32,
-    /// apu register $4008,$4009,$400A,$400B,
-    // triangle: u32,
-    /// apu register $400C,$400D,$400E,$400F,
-    // noise: u32,
-    /// apu register $4010,$4011,$4012,$4013,
-    // dmc: u32,
-    /// apu register $4015
-    // status: u8,
-    /// apu register $4017
-    /// also called frame counter in docs
-    // frame_sequencer: u8,
-    registers: [u8; 0x18],
+/// a flat 64KB ram with no mirroring or memory mapped devices,
+/// for running generic 6502 programs and cpu tests
+pub struct FlatRam {
+    inner: Box<[u8; 0x1_0000]>,
+}
+
+impl Default for FlatRam {
+    fn default() -> Self {
+        FlatRam {
+            inner: Box::new([0u8; 0x1_0000]),
+        }
+    }
 }
 
-impl Bus for Nes2a03Audio {
+impl Bus for FlatRam {
     fn bounds(&self) -> (u16, u16) {
-        (0x4000, 0x401F)
+        (0, 0xffff)
     }
     fn set(&mut self, address: u16, value: u8) {
-        let offset = address.wrapping_sub(0x4000);
-        // println!("check: {} vs {}", address, offset);
-        if offset < 0x18 {
-            self.registers[offset as usize] = value;
-        } else {
-            panic!(
-                "attempted to set invalid memory range in apu. {:04X} = {:02X}",
-                address, value
-            );
-        }
+        self.inner[address as usize] = value;
     }
     fn get(&mut self, address: u16) -> u8 {
-        let offset = address.wrapping_sub(0x4000);
-        // println!("check: {} vs {}", address, offset);
-        if offset < 0x18 {
-            self.registers[offset as usize]
-        } else {
-            panic!(
-                "attempted to get invalid memory range in apu. {:04X}",
-                address
-            );
-        }
+        self.inner[address as usize]
     }
 }
-
-#[derive(Default)]
-pub struct NesGamepad;
@@ -1,7 +1,13 @@
+use crate::prelude::Mirroring;
+
 pub trait Mapper {
     fn translate(&self, address: u16) -> u16 {
         address
     }
+    /// nametable arrangement chosen by the mapper, or None to use the one wired on the cartridge
+    fn mirroring(&self) -> Option<Mirroring> {
+        None
+    }
 }
 
 
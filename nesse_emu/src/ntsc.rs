@@ -0,0 +1,213 @@
+//! an ntsc composite video filter in the style of blargg's nes_ntsc.
+//! each pixel becomes the square wave the ppu would put on the video cable, 8 samples
+//! per pixel against a 12 phase color subcarrier, which is then decoded back to rgb the
+//! way a tv would. luma and chroma share the signal, so they bleed into each other,
+//! and the subcarrier phase moves every scanline and every frame, which makes dots crawl.
+use crate::palette::VideoFilter;
+use crate::ppu::{FRAME_HEIGHT, FRAME_WIDTH};
+
+/// signal samples per pixel
+const SAMPLES_PER_PIXEL: usize = 8;
+/// samples in one cycle of the color subcarrier
+const SUBCARRIER_PHASES: usize = 12;
+/// the phase moves along by 341 * 8 samples each scanline
+const PHASE_PER_SCANLINE: usize = 341 * SAMPLES_PER_PIXEL % SUBCARRIER_PHASES;
+/// output pixels for the 256 pixels of a scanline, as nes_ntsc makes
+pub const NTSC_OUTPUT_WIDTH: usize = 602;
+
+/// signal voltages for the four brightness levels, low and high halves of the wave
+const LOW_LEVELS: [f32; 4] = [0.350, 0.518, 0.962, 1.550];
+const HIGH_LEVELS: [f32; 4] = [1.094, 1.506, 1.962, 1.962];
+const BLACK: f32 = 0.518;
+const WHITE: f32 = 1.962;
+/// how much an emphasis bit lowers the signal during its third of the wave
+const EMPHASIS_ATTENUATION: f32 = 0.746;
+/// lines the decoder's color reference up with the ppu's, in samples
+const HUE_OFFSET: f32 = 3.9;
+
+/// filter settings, each from -1 to 1 or 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NtscSetup {
+    /// -1 blurs, 1 sharpens the edges in luma
+    pub sharpness: f32,
+    /// 0 to 1, how much chroma leaks into luma as dot crawl and rainbows
+    pub artifacts: f32,
+    /// 0 to 1, how far color smears across neighbouring pixels
+    pub bleed: f32,
+    /// average in the next frame's phase, hiding the crawl the way a slow screen does
+    pub merge_fields: bool,
+}
+
+impl NtscSetup {
+    /// a composite cable, with all the artifacts
+    pub fn composite() -> NtscSetup {
+        NtscSetup {
+            sharpness: 0.0,
+            artifacts: 1.0,
+            bleed: 0.5,
+            merge_fields: false,
+        }
+    }
+    /// s-video keeps luma and chroma apart, so only the color bleed is left
+    pub fn svideo() -> NtscSetup {
+        NtscSetup {
+            sharpness: 0.2,
+            artifacts: 0.0,
+            bleed: 0.2,
+            merge_fields: false,
+        }
+    }
+}
+
+impl Default for NtscSetup {
+    fn default() -> NtscSetup {
+        NtscSetup::composite()
+    }
+}
+
+/// turns frames of pixels into ntsc_output_width wide rgb
+pub struct NtscFilter {
+    setup: NtscSetup,
+    /// cos and sin of each subcarrier phase, for demodulating chroma
+    carrier: [(f32, f32); SUBCARRIER_PHASES],
+    /// the signal of the scanline being decoded
+    signal: Vec<f32>,
+}
+
+impl NtscFilter {
+    pub fn new(setup: NtscSetup) -> NtscFilter {
+        let mut carrier = [(0.0, 0.0); SUBCARRIER_PHASES];
+        for (phase, wave) in carrier.iter_mut().enumerate() {
+            let angle = std::f32::consts::PI * (phase as f32 + HUE_OFFSET) / 6.0;
+            *wave = (angle.cos(), angle.sin());
+        }
+        NtscFilter {
+            setup,
+            carrier,
+            signal: vec![0.0; FRAME_WIDTH * SAMPLES_PER_PIXEL],
+        }
+    }
+    pub fn setup(&self) -> NtscSetup {
+        self.setup
+    }
+    pub fn set_setup(&mut self, setup: NtscSetup) {
+        self.setup = setup;
+    }
+    /// the signal level of a pixel at one phase of the subcarrier, 0 for black and 1 for white
+    fn signal_level(pixel: u16, phase: usize) -> f32 {
+        let color = (pixel & 0x0f) as usize;
+        let level = if color > 0x0d {
+            1
+        } else {
+            ((pixel >> 4) & 0b11) as usize
+        };
+        let mut low = LOW_LEVELS[level];
+        let mut high = HIGH_LEVELS[level];
+        if color == 0 {
+            low = high;
+        } else if color > 0x0c {
+            high = low;
+        }
+        let in_phase = |color: usize| (color + phase) % SUBCARRIER_PHASES < 6;
+        let mut signal = if in_phase(color) { high } else { low };
+        // red, green and blue emphasis each dim a third of the wave
+        let emphasis = pixel >> 6;
+        if (emphasis & 0b001 > 0 && in_phase(0x0))
+            || (emphasis & 0b010 > 0 && in_phase(0x4))
+            || (emphasis & 0b100 > 0 && in_phase(0x8))
+        {
+            signal *= EMPHASIS_ATTENUATION;
+        }
+        (signal - BLACK) / (WHITE - BLACK)
+    }
+    /// averages the signal around a sample, optionally demodulating it against the carrier
+    fn average(&self, center: f32, width: usize, phase: usize, chroma: bool) -> (f32, f32, f32) {
+        let start = center as isize - width as isize / 2;
+        let mut sums = (0.0, 0.0, 0.0);
+        for sample in start..start + width as isize {
+            let level = if sample < 0 || sample >= self.signal.len() as isize {
+                0.0
+            } else {
+                self.signal[sample as usize]
+            };
+            sums.0 += level;
+            if chroma {
+                let phase = (phase as isize + sample).rem_euclid(SUBCARRIER_PHASES as isize);
+                let (cos, sin) = self.carrier[phase as usize];
+                sums.1 += level * cos;
+                sums.2 += level * sin;
+            }
+        }
+        let width = width as f32;
+        (sums.0 / width, sums.1 / width, sums.2 / width)
+    }
+    /// encodes and decodes one scanline
+    fn scanline(&mut self, pixels: &[u16], phase: usize, rgb: &mut [f32]) {
+        for (x, pixel) in pixels.iter().enumerate() {
+            for sample in 0..SAMPLES_PER_PIXEL {
+                let n = x * SAMPLES_PER_PIXEL + sample;
+                self.signal[n] = Self::signal_level(*pixel, (phase + n) % SUBCARRIER_PHASES);
+            }
+        }
+        // a full subcarrier cycle cancels chroma out of luma, half of one lets it through.
+        // chroma is averaged over whole cycles so flat colors stay flat
+        let artifacts = self.setup.artifacts.clamp(0.0, 1.0);
+        let bleed = self.setup.bleed.clamp(0.0, 1.0);
+        let scale = self.signal.len() as f32 / NTSC_OUTPUT_WIDTH as f32;
+        for (x, out) in rgb.chunks_exact_mut(3).enumerate() {
+            let center = (x as f32 + 0.5) * scale;
+            let (clean, _, _) = self.average(center, SUBCARRIER_PHASES, phase, false);
+            let (leaky, _, _) = self.average(center, SUBCARRIER_PHASES / 2, phase, false);
+            let mut y = clean + (leaky - clean) * artifacts;
+            if self.setup.sharpness != 0.0 {
+                let (wide, _, _) = self.average(center, SUBCARRIER_PHASES * 2, phase, false);
+                y += (y - wide) * self.setup.sharpness;
+            }
+            let (_, narrow_i, narrow_q) = self.average(center, SUBCARRIER_PHASES, phase, true);
+            let (_, wide_i, wide_q) = self.average(center, SUBCARRIER_PHASES * 2, phase, true);
+            let i = narrow_i + (wide_i - narrow_i) * bleed;
+            let q = narrow_q + (wide_q - narrow_q) * bleed;
+            // yiq to rgb, with the demodulated chroma at half amplitude
+            let (i, q) = (i * 2.0, q * 2.0);
+            out[0] += y + 0.946882 * i + 0.623557 * q;
+            out[1] += y - 0.274788 * i - 0.635691 * q;
+            out[2] += y - 1.108545 * i + 1.709007 * q;
+        }
+    }
+}
+
+impl Default for NtscFilter {
+    fn default() -> NtscFilter {
+        NtscFilter::new(NtscSetup::default())
+    }
+}
+
+impl VideoFilter for NtscFilter {
+    fn output_width(&self) -> usize {
+        NTSC_OUTPUT_WIDTH
+    }
+    fn apply(&mut self, pixels: &[u16], burst_phase: u8, rgb: &mut [u8]) {
+        let mut line = vec![0.0f32; NTSC_OUTPUT_WIDTH * 3];
+        let fields: &[usize] = if self.setup.merge_fields {
+            &[0, 4]
+        } else {
+            &[0]
+        };
+        let lines = pixels.chunks_exact(FRAME_WIDTH).take(FRAME_HEIGHT);
+        for (y, (pixels, out)) in lines
+            .zip(rgb.chunks_exact_mut(NTSC_OUTPUT_WIDTH * 3))
+            .enumerate()
+        {
+            line.iter_mut().for_each(|value| *value = 0.0);
+            for field in fields {
+                let phase =
+                    (burst_phase as usize + y * PHASE_PER_SCANLINE + field) % SUBCARRIER_PHASES;
+                self.scanline(pixels, phase, &mut line);
+            }
+            let fields = fields.len() as f32;
+            for (value, out) in line.iter().zip(out.iter_mut()) {
+                *out = (value / fields * 255.0).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
@@ -1,52 +1,76 @@
 #![doc = r" generated in nesse_codegen, in generate_generate_stub_opcode_implementations,"]
 #![doc = r" edited by hand afterwards"]
 
-use crate::{Bus, Nes, RegisterAccess};
+use crate::cpu::Processor;
+use crate::STACK_INITIAL;
 
 pub mod jumptable;
+pub mod jumptable_65c02;
+pub mod jumptable_nmos;
 pub mod opcode_debug;
 
+/// 1 if the two addresses are on different pages
+fn page_crossed(from: u16, to: u16) -> u8 {
+    (from & 0xff00 != to & 0xff00) as u8
+}
+
+/// helper function for instructions that only read memory: indexing across
+/// a page boundary costs them an extra cycle. must be called before the index register changes
+fn page_cross_penalty<P: Processor>(cpu: &P, addressing: u8, address: u16) -> u8 {
+    let index = match addressing {
+        // AbsoluteX
+        8 => cpu.get_x(),
+        // AbsoluteY, IndirectIndexed
+        9 | 12 => cpu.get_y(),
+        _ => return 0,
+    };
+    page_crossed(address.wrapping_sub(index as u16), address)
+}
+
 // load & store family --------------------------------------------------------
-pub fn ldx(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address);
-    nes.cpu.set_x(value);
-    nes.cpu.set_flags_from(value);
-    cycles
+pub fn ldx<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let value = cpu.get(address);
+    cpu.set_x(value);
+    cpu.set_flags_from(value);
+    cycles + penalty
 }
 
-pub fn lda(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address);
-    nes.cpu.set_a(value);
-    nes.cpu.set_flags_from(value);
-    cycles
+pub fn lda<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let value = cpu.get(address);
+    cpu.set_a(value);
+    cpu.set_flags_from(value);
+    cycles + penalty
 }
 
-pub fn ldy(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address);
-    nes.cpu.set_y(value);
-    nes.cpu.set_flags_from(value);
-    cycles
+pub fn ldy<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let value = cpu.get(address);
+    cpu.set_y(value);
+    cpu.set_flags_from(value);
+    cycles + penalty
 }
 
-pub fn sta(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    nes.set(address, nes.cpu.get_a());
+pub fn sta<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    cpu.set(address, cpu.get_a());
     cycles
 }
 
-pub fn stx(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    nes.set(address, nes.cpu.get_x());
+pub fn stx<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    cpu.set(address, cpu.get_x());
 
     cycles
 }
 
-pub fn sty(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    nes.set(address, nes.cpu.get_y());
+pub fn sty<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    cpu.set(address, cpu.get_y());
 
     cycles
 }
@@ -54,156 +78,156 @@ pub fn sty(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
 // stack-related loads and stores ---------------------------------------
 
 /// push p to stack
-pub fn php(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_status_stack();
-    nes.stack_push(value);
+pub fn php<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_status_stack();
+    cpu.stack_push(value);
     cycles
 }
 
 /// pull p from stack
-pub fn plp(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.stack_pop();
-    nes.cpu.set_status_stack(value);
+pub fn plp<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.stack_pop();
+    cpu.set_status_stack(value);
     cycles
 }
 
 /// pull a from stack
-pub fn pla(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.stack_pop();
-    nes.cpu.set_flags_from(value);
-    nes.cpu.set_a(value);
+pub fn pla<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.stack_pop();
+    cpu.set_flags_from(value);
+    cpu.set_a(value);
     cycles
 }
 
 /// push a to stack
-pub fn pha(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_a();
-    nes.stack_push(value);
+pub fn pha<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_a();
+    cpu.stack_push(value);
     cycles
 }
 
 /// transfer stackpointer to x
-pub fn tsx(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_sp();
-    nes.cpu.set_x(value);
-    nes.cpu.set_flags_from(value);
+pub fn tsx<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_sp();
+    cpu.set_x(value);
+    cpu.set_flags_from(value);
     cycles
 }
 
 /// transfer x to stackpointer
-pub fn txs(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_x();
-    nes.cpu.set_sp(value);
+pub fn txs<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_x();
+    cpu.set_sp(value);
     cycles
 }
 
 // registers-only family -------------------------------------------------
-pub fn tax(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_a();
-    nes.cpu.set_x(value);
-    nes.cpu.set_flags_from(value);
+pub fn tax<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_a();
+    cpu.set_x(value);
+    cpu.set_flags_from(value);
     cycles
 }
-pub fn txa(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_x();
-    nes.cpu.set_a(value);
-    nes.cpu.set_flags_from(value);
+pub fn txa<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_x();
+    cpu.set_a(value);
+    cpu.set_flags_from(value);
     cycles
 }
 
-pub fn tay(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_a();
-    nes.cpu.set_y(value);
-    nes.cpu.set_flags_from(value);
+pub fn tay<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_a();
+    cpu.set_y(value);
+    cpu.set_flags_from(value);
     cycles
 }
 
-pub fn tya(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_y();
-    nes.cpu.set_a(value);
-    nes.cpu.set_flags_from(value);
+pub fn tya<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_y();
+    cpu.set_a(value);
+    cpu.set_flags_from(value);
     cycles
 }
 
-pub fn inx(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_x().wrapping_add(1);
-    nes.cpu.set_x(value);
-    nes.cpu.set_flags_from(value);
+pub fn inx<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_x().wrapping_add(1);
+    cpu.set_x(value);
+    cpu.set_flags_from(value);
     cycles
 }
-pub fn iny(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_y().wrapping_add(1);
-    nes.cpu.set_y(value);
-    nes.cpu.set_flags_from(value);
+pub fn iny<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_y().wrapping_add(1);
+    cpu.set_y(value);
+    cpu.set_flags_from(value);
     cycles
 }
 
-pub fn dex(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_x().wrapping_sub(1);
-    nes.cpu.set_x(value);
-    nes.cpu.set_flags_from(value);
+pub fn dex<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_x().wrapping_sub(1);
+    cpu.set_x(value);
+    cpu.set_flags_from(value);
     cycles
 }
-pub fn dey(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let value = nes.cpu.get_y().wrapping_sub(1);
-    nes.cpu.set_y(value);
-    nes.cpu.set_flags_from(value);
+pub fn dey<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_y().wrapping_sub(1);
+    cpu.set_y(value);
+    cpu.set_flags_from(value);
     cycles
 }
 
-pub fn clc(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    nes.cpu.clear_carry();
+pub fn clc<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.clear_carry();
     cycles
 }
 
-pub fn sec(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    nes.cpu.set_carry();
+pub fn sec<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.set_carry();
     cycles
 }
 
-pub fn sed(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    nes.cpu.set_decimal();
+pub fn sed<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.set_decimal();
     cycles
 }
 
-pub fn cld(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    nes.cpu.clear_decimal();
+pub fn cld<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.clear_decimal();
     cycles
 }
 
-pub fn clv(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    nes.cpu.clear_overflow();
+pub fn clv<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.clear_overflow();
     cycles
 }
 
 // interrupts family ------------------------------------------------------
-pub fn brk(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    nes.cpu.registers.pc -= 1;
-    nes.cpu.running = false;
+pub fn brk<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.set_pc(cpu.get_pc().wrapping_sub(1));
+    cpu.halt();
     cycles
 }
 
-pub fn nop(_nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn nop<P: Processor>(_cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     cycles
 }
 
-pub fn sei(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    nes.cpu.registers.set_interrupt();
+pub fn sei<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.set_interrupt();
     cycles
 }
-pub fn cli(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    nes.cpu.registers.clear_interrupt();
+pub fn cli<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.clear_interrupt();
     cycles
 }
 
 /// return from interrupt
-pub fn rti(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let flags = nes.stack_pop();
-    let value = nes.stack_pop_short();
+pub fn rti<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let flags = cpu.stack_pop();
+    let value = cpu.stack_pop_short();
     // add one back to the value we got since we subtracted one in jsr
     // let pc = value.wrapping_add(1); // todo: why dont we add 1 here like rts?
-    nes.cpu.registers.pc = value;
-    nes.cpu.set_status_stack(flags);
+    cpu.set_pc(value);
+    cpu.set_status_stack(flags);
 
     cycles
 }
@@ -212,592 +236,927 @@ pub fn rti(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
 
 /// helper function to add a value to register a, setting appropriate flags
 /// including the carry flag
-fn add_to_a(nes: &mut Nes, input: u8) {
-    let carry = nes.cpu.registers.get_carry();
-    let value = input as u16 + nes.cpu.registers.a as u16 + carry as u16;
+fn add_to_a<P: Processor>(cpu: &mut P, input: u8) {
+    let carry = cpu.get_carry();
+    let value = input as u16 + cpu.get_a() as u16 + carry as u16;
     let low_value = (value & 0xff) as u8;
 
-    nes.cpu.registers.set_flags(low_value);
+    cpu.set_flags_from(low_value);
     if value > 0xff {
-        nes.cpu.registers.set_carry();
+        cpu.set_carry();
+    } else {
+        cpu.clear_carry();
+    }
+    if (input ^ low_value) & (low_value ^ cpu.get_a()) & 0x80 != 0 {
+        cpu.set_overflow();
+    } else {
+        cpu.clear_overflow();
+    }
+    cpu.set_a(low_value);
+}
+
+pub fn adc<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let input = cpu.get(address);
+    add_to_a(cpu, input);
+    cycles + penalty
+}
+
+pub fn sbc<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let input = cpu.get(address) as i8;
+    add_to_a(cpu, input.wrapping_neg().wrapping_sub(1) as u8);
+    cycles + penalty
+}
+
+/// helper function to add a value to register a in binary coded decimal, as the nmos 6502 does.
+/// only the carry and accumulator are valid bcd results, the other flags
+/// are set from intermediate values of the calculation
+fn add_to_a_decimal<P: Processor>(cpu: &mut P, input: u8) {
+    let a = cpu.get_a();
+    let carry = cpu.get_carry();
+    let mut lo = (a & 0x0f) + (input & 0x0f) + carry;
+    if lo > 9 {
+        lo += 6;
+    }
+    let mut hi = (a >> 4) + (input >> 4) + (lo > 0x0f) as u8;
+    let binary = a.wrapping_add(input).wrapping_add(carry);
+    cpu.set_zero_from(binary);
+    // the negative flag is taken from the high nibble before it is adjusted
+    cpu.set_negative_from(hi << 4);
+    if !(a ^ input) & (a ^ (hi << 4)) & 0x80 != 0 {
+        cpu.set_overflow();
+    } else {
+        cpu.clear_overflow();
+    }
+    if hi > 9 {
+        hi += 6;
+    }
+    if hi > 0x0f {
+        cpu.set_carry();
+    } else {
+        cpu.clear_carry();
+    }
+    cpu.set_a((hi << 4) | (lo & 0x0f));
+}
+
+/// helper function to subtract a value from register a in binary coded decimal, as the nmos 6502 does.
+/// all flags are set as for a binary subtraction, only the accumulator differs
+fn subtract_from_a_decimal<P: Processor>(cpu: &mut P, input: u8) {
+    let a = cpu.get_a();
+    let borrow = 1 - cpu.get_carry();
+    let mut lo = (a & 0x0f) as i16 - (input & 0x0f) as i16 - borrow as i16;
+    let mut hi = (a >> 4) as i16 - (input >> 4) as i16;
+    if lo < 0 {
+        lo -= 6;
+        hi -= 1;
+    }
+    if hi < 0 {
+        hi -= 6;
+    }
+    // flags come from the binary result
+    add_to_a(cpu, !input);
+    cpu.set_a(((hi as u8) << 4) | (lo as u8 & 0x0f));
+}
+
+/// helper function for the 65c02's decimal adc, which leaves n and z valid for the bcd result
+fn add_to_a_decimal_cmos<P: Processor>(cpu: &mut P, input: u8) {
+    let a = cpu.get_a();
+    let carry = cpu.get_carry() as i16;
+    let mut lo = (a & 0x0f) as i16 + (input & 0x0f) as i16 + carry;
+    if lo >= 0x0a {
+        lo = ((lo + 0x06) & 0x0f) + 0x10;
+    }
+    // overflow is calculated with signed arithmetic on the high nibbles
+    let signed = (a & 0xf0) as i8 as i16 + (input & 0xf0) as i8 as i16 + lo;
+    if !(-128..=127).contains(&signed) {
+        cpu.set_overflow();
     } else {
-        nes.cpu.registers.clear_carry();
+        cpu.clear_overflow();
+    }
+    let mut sum = (a & 0xf0) as i16 + (input & 0xf0) as i16 + lo;
+    if sum >= 0xa0 {
+        sum += 0x60;
+    }
+    if sum >= 0x100 {
+        cpu.set_carry();
+    } else {
+        cpu.clear_carry();
+    }
+    let result = (sum & 0xff) as u8;
+    cpu.set_flags_from(result);
+    cpu.set_a(result);
+}
+
+/// helper function for the 65c02's decimal sbc, which leaves n and z valid for the bcd result
+fn subtract_from_a_decimal_cmos<P: Processor>(cpu: &mut P, input: u8) {
+    let a = cpu.get_a();
+    let borrow = 1 - cpu.get_carry() as i16;
+    let lo = (a & 0x0f) as i16 - (input & 0x0f) as i16 - borrow;
+    let mut difference = a as i16 - input as i16 - borrow;
+    if difference < 0 {
+        difference -= 0x60;
+    }
+    if lo < 0 {
+        difference -= 0x06;
     }
-    if (input ^ low_value) & (low_value ^ nes.cpu.registers.a) & 0x80 != 0 {
-        nes.cpu.registers.set_overflow();
+    // carry and overflow come from the binary result
+    add_to_a(cpu, !input);
+    let result = (difference & 0xff) as u8;
+    cpu.set_flags_from(result);
+    cpu.set_a(result);
+}
+
+/// nmos 6502 adc, which honours the decimal flag
+pub fn adc_bcd<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let input = cpu.get(address);
+    if cpu.status_decimal() {
+        add_to_a_decimal(cpu, input);
     } else {
-        nes.cpu.registers.clear_overflow();
+        add_to_a(cpu, input);
     }
-    nes.cpu.registers.a = low_value;
+    cycles + penalty
 }
 
-pub fn adc(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let input = nes.get(address);
-    add_to_a(nes, input);
-    cycles
+/// nmos 6502 sbc, which honours the decimal flag
+pub fn sbc_bcd<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let input = cpu.get(address);
+    if cpu.status_decimal() {
+        subtract_from_a_decimal(cpu, input);
+    } else {
+        add_to_a(cpu, !input);
+    }
+    cycles + penalty
 }
 
-pub fn sbc(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let input = nes.get(address) as i8;
-    add_to_a(nes, input.wrapping_neg().wrapping_sub(1) as u8);
-    cycles
+/// 65c02 adc, which takes an extra cycle in decimal mode
+pub fn adc_cmos<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let input = cpu.get(address);
+    if cpu.status_decimal() {
+        add_to_a_decimal_cmos(cpu, input);
+        cycles + 1 + penalty
+    } else {
+        add_to_a(cpu, input);
+        cycles + penalty
+    }
 }
 
-pub fn and(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address) & nes.cpu.get_a();
-    nes.cpu.set_a(value);
-    nes.cpu.set_flags_from(value);
-    cycles
+/// 65c02 sbc, which takes an extra cycle in decimal mode
+pub fn sbc_cmos<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let input = cpu.get(address);
+    if cpu.status_decimal() {
+        subtract_from_a_decimal_cmos(cpu, input);
+        cycles + 1 + penalty
+    } else {
+        add_to_a(cpu, !input);
+        cycles + penalty
+    }
+}
+
+pub fn and<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let value = cpu.get(address) & cpu.get_a();
+    cpu.set_a(value);
+    cpu.set_flags_from(value);
+    cycles + penalty
 }
-pub fn lsr(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn lsr<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     // todo: this is gross, fix it fix it fix it
     if addressing == 1 {
         // operate on the accumulator
-        let mut value = nes.cpu.get_a();
+        let mut value = cpu.get_a();
         if value & 0b1 == 0b1 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value >>= 1;
-        nes.cpu.set_flags_from(value);
-        nes.cpu.set_a(value);
+        cpu.set_flags_from(value);
+        cpu.set_a(value);
     } else {
         // operate per addressing mode
-        let address = nes.get_address_from_mode(addressing);
-        let mut value = nes.get(address);
+        let address = cpu.address_from_mode(addressing);
+        let mut value = cpu.get(address);
         if value & 0b1 == 0b1 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value >>= 1;
-        nes.cpu.set_flags_from(value);
-        nes.set(address, value);
+        cpu.set_flags_from(value);
+        cpu.set(address, value);
     }
     cycles
 }
 
-pub fn dec(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address).wrapping_sub(1);
-    nes.set(address, value);
-    nes.cpu.set_flags_from(value);
+pub fn dec<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    if addressing == 1 {
+        // operate on the accumulator, 65c02 only
+        let value = cpu.get_a().wrapping_sub(1);
+        cpu.set_a(value);
+        cpu.set_flags_from(value);
+    } else {
+        let address = cpu.address_from_mode(addressing);
+        let value = cpu.get(address).wrapping_sub(1);
+        cpu.set(address, value);
+        cpu.set_flags_from(value);
+    }
     cycles
 }
-pub fn inc(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address).wrapping_add(1);
-    nes.set(address, value);
-    nes.cpu.set_flags_from(value);
+pub fn inc<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    if addressing == 1 {
+        // operate on the accumulator, 65c02 only
+        let value = cpu.get_a().wrapping_add(1);
+        cpu.set_a(value);
+        cpu.set_flags_from(value);
+    } else {
+        let address = cpu.address_from_mode(addressing);
+        let value = cpu.get(address).wrapping_add(1);
+        cpu.set(address, value);
+        cpu.set_flags_from(value);
+    }
     cycles
 }
 
-pub fn ora(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address) | nes.cpu.get_a();
-    nes.cpu.set_a(value);
-    nes.cpu.set_flags_from(value);
-    cycles
+pub fn ora<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let value = cpu.get(address) | cpu.get_a();
+    cpu.set_a(value);
+    cpu.set_flags_from(value);
+    cycles + penalty
 }
 
-pub fn eor(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address) ^ nes.cpu.get_a();
-    nes.cpu.set_a(value);
-    nes.cpu.set_flags_from(value);
-    cycles
+pub fn eor<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let value = cpu.get(address) ^ cpu.get_a();
+    cpu.set_a(value);
+    cpu.set_flags_from(value);
+    cycles + penalty
 }
 
-pub fn asl(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn asl<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     if addressing == 1 {
         // operate on the accumulator
-        let mut value = nes.cpu.get_a();
+        let mut value = cpu.get_a();
         if value & 1 << 7 == 1 << 7 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value <<= 1;
-        nes.cpu.set_flags_from(value);
-        nes.cpu.set_a(value);
+        cpu.set_flags_from(value);
+        cpu.set_a(value);
     } else {
         // operate per addressing mode
-        let address = nes.get_address_from_mode(addressing);
-        let mut value = nes.get(address);
+        let address = cpu.address_from_mode(addressing);
+        let mut value = cpu.get(address);
         if value & 1 << 7 == 1 << 7 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value <<= 1;
-        nes.cpu.set_flags_from(value);
-        nes.set(address, value);
+        cpu.set_flags_from(value);
+        cpu.set(address, value);
     }
     cycles
 }
 
-pub fn ror(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn ror<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     if addressing == 1 {
         // operate on the accumulator
-        let mut value = nes.cpu.get_a();
-        let carry = nes.cpu.registers.get_carry() << 7;
+        let mut value = cpu.get_a();
+        let carry = cpu.get_carry() << 7;
         if value & 1 == 1 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value >>= 1;
         value |= carry;
-        nes.cpu.set_flags_from(value);
-        nes.cpu.set_a(value);
+        cpu.set_flags_from(value);
+        cpu.set_a(value);
     } else {
         // operate per addressing mode
-        let address = nes.get_address_from_mode(addressing);
-        let mut value = nes.get(address);
-        let carry = nes.cpu.registers.get_carry() << 7;
+        let address = cpu.address_from_mode(addressing);
+        let mut value = cpu.get(address);
+        let carry = cpu.get_carry() << 7;
         if value & 1 == 1 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value >>= 1;
         value |= carry;
-        nes.cpu.set_flags_from(value);
-        nes.set(address, value);
+        cpu.set_flags_from(value);
+        cpu.set(address, value);
     }
     cycles
 }
 
-pub fn rol(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn rol<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     if addressing == 1 {
         // operate on the accumulator
-        let mut value = nes.cpu.get_a();
-        let carry = nes.cpu.registers.get_carry();
+        let mut value = cpu.get_a();
+        let carry = cpu.get_carry();
         if value & 1 << 7 == 1 << 7 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value <<= 1;
         value |= carry;
-        nes.cpu.set_flags_from(value);
-        nes.cpu.set_a(value);
+        cpu.set_flags_from(value);
+        cpu.set_a(value);
     } else {
         // operate per addressing mode
-        let address = nes.get_address_from_mode(addressing);
-        let mut value = nes.get(address);
-        let carry = nes.cpu.registers.get_carry();
+        let address = cpu.address_from_mode(addressing);
+        let mut value = cpu.get(address);
+        let carry = cpu.get_carry();
         if value & 1 << 7 == 1 << 7 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value <<= 1;
         value |= carry;
-        nes.cpu.set_flags_from(value);
-        nes.set(address, value);
+        cpu.set_flags_from(value);
+        cpu.set(address, value);
     }
     cycles
 }
 
 // compare family --------------------------------------------------------------
-pub fn cmp(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address);
-    let compare = nes.cpu.registers.a as i16 - value as i16;
+pub fn cmp<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let value = cpu.get(address);
+    let compare = cpu.get_a() as i16 - value as i16;
     if compare >= 0 {
-        nes.cpu.registers.set_carry();
+        cpu.set_carry();
     } else {
-        nes.cpu.registers.clear_carry();
+        cpu.clear_carry();
     }
-    nes.cpu.registers.set_flags(compare as u8);
-    cycles
+    cpu.set_flags_from(compare as u8);
+    cycles + penalty
 }
-pub fn cpx(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address);
-    let compare = nes.cpu.registers.x as i16 - value as i16;
+pub fn cpx<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get(address);
+    let compare = cpu.get_x() as i16 - value as i16;
     if compare >= 0 {
-        nes.cpu.registers.set_carry();
+        cpu.set_carry();
     } else {
-        nes.cpu.registers.clear_carry();
+        cpu.clear_carry();
     }
-    nes.cpu.registers.set_flags(compare as u8);
+    cpu.set_flags_from(compare as u8);
     cycles
 }
-pub fn cpy(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address);
-    let compare = nes.cpu.registers.y as i16 - value as i16;
+pub fn cpy<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get(address);
+    let compare = cpu.get_y() as i16 - value as i16;
     if compare >= 0 {
-        nes.cpu.registers.set_carry();
+        cpu.set_carry();
     } else {
-        nes.cpu.registers.clear_carry();
+        cpu.clear_carry();
     }
-    nes.cpu.registers.set_flags(compare as u8);
+    cpu.set_flags_from(compare as u8);
     cycles
 }
 
-pub fn bit(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address);
-    let mask = nes.cpu.get_a();
+pub fn bit<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get(address);
+    let mask = cpu.get_a();
     let result = value & mask;
 
-    nes.cpu.registers.set_overflow_from(value);
-    nes.cpu.registers.set_negative_from(value);
-    nes.cpu.registers.set_zero_from(result);
+    if addressing != 2 {
+        // the 65c02's immediate mode only affects the zero flag
+        cpu.set_overflow_from(value);
+        cpu.set_negative_from(value);
+    }
+    cpu.set_zero_from(result);
 
     cycles
 }
 
 // jump & branch family ----------------------------------------------------------------
-pub fn jsr(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let jump_address = nes.get_short(nes.cpu.registers.pc);
+pub fn jsr<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let jump_address = cpu.get_short(cpu.get_pc());
 
     // todo: validate this stays correct with tests
     // skip two bytes because above call didn't advance PC when reading 2 bytes
     // but subtract one from the value because its a quirk of the cpu evidently
-    let return_address = nes.cpu.registers.pc + 2 - 1;
-    nes.stack_push_short(return_address);
-    nes.cpu.registers.pc = jump_address;
+    let return_address = cpu.get_pc() + 2 - 1;
+    cpu.stack_push_short(return_address);
+    cpu.set_pc(jump_address);
     cycles
 }
-pub fn jmp(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    nes.cpu.registers.pc = address;
+pub fn jmp<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    cpu.set_pc(address);
     cycles
 }
-pub fn rts(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    if nes.cpu.registers.sp != super::STACK_INITIAL {
-        let value = nes.stack_pop_short();
+pub fn rts<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    if cpu.get_sp() != STACK_INITIAL {
+        let value = cpu.stack_pop_short();
         // add one back to the value we got since we subtracted one in jsr
         // todo: add tests to show this has the right value
         let pc = value.wrapping_add(1);
-        nes.cpu.registers.pc = pc;
+        cpu.set_pc(pc);
         cycles
     } else {
         // main loop is over?
         println!("rts resulted in stack underflow. stopping.");
-        nes.cpu.registers.pc -= 1;
-        nes.cpu.running = false;
+        cpu.set_pc(cpu.get_pc().wrapping_sub(1));
+        cpu.halt();
         cycles
     }
 }
 
-pub fn beq(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let offset = nes.get(nes.cpu.registers.pc) as u16;
-    nes.cpu.registers.pc = nes.cpu.registers.pc.wrapping_add(1);
-    if nes.cpu.registers.status_zero() {
-        let pc = nes.cpu.registers.pc.wrapping_add(offset);
-        nes.cpu.registers.pc = pc;
+/// helper function for the conditional branches. reads the offset and moves pc if the condition holds,
+/// taking an extra cycle when the branch is taken and another if it lands on a different page
+fn branch_if<P: Processor>(cpu: &mut P, condition: bool, cycles: u8) -> u8 {
+    let offset = cpu.get(cpu.get_pc()) as i8;
+    let pc = cpu.get_pc().wrapping_add(1);
+    if condition {
+        let destination = pc.wrapping_add(offset as u16);
+        cpu.set_pc(destination);
+        cycles + 1 + page_crossed(pc, destination)
+    } else {
+        cpu.set_pc(pc);
+        cycles
     }
-    cycles
 }
 
-pub fn bne(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    if !nes.cpu.registers.status_zero() {
-        let offset = nes.get(nes.cpu.registers.pc) as i8;
-        let new_pc = nes.cpu.registers.pc
-        .wrapping_add(1) // add one to advance PC past the above read
-        as i32; // upcast to i32 in order to avoid clipping
-        let pc = ((new_pc + offset as i32) & 0xffff) as u16;
-        nes.cpu.registers.pc = pc;
-    } else {
-        nes.cpu.registers.pc = nes.cpu.registers.pc.wrapping_add(1);
-    }
-    cycles
+pub fn beq<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let condition = cpu.status_zero();
+    branch_if(cpu, condition, cycles)
 }
 
-pub fn bpl(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let offset = nes.get(nes.cpu.registers.pc) as i8;
-    nes.cpu.registers.pc = nes.cpu.registers.pc.wrapping_add(1);
-    if !nes.cpu.registers.status_negative() {
-        let mut pc = nes.cpu.registers.pc as i32;
-        pc = (pc + offset as i32) & 0xffff;
-        nes.cpu.registers.pc = pc as u16;
-    }
-    cycles
+pub fn bne<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let condition = !cpu.status_zero();
+    branch_if(cpu, condition, cycles)
 }
 
-pub fn bcs(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    if nes.cpu.registers.status_carry() {
-        let offset = nes.get(nes.cpu.registers.pc) as i8;
-        let mut pc = nes.cpu.registers.pc.wrapping_add(1) as i32; // increment......
-        pc = (pc + (offset as i32)) & 0xffff;
-        nes.cpu.registers.pc = pc as u16;
-    } else {
-        nes.cpu.registers.pc = nes.cpu.registers.pc.wrapping_add(1);
-    }
-    cycles
+pub fn bpl<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let condition = !cpu.status_negative();
+    branch_if(cpu, condition, cycles)
 }
 
-pub fn bcc(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let offset = nes.get(nes.cpu.registers.pc) as i8;
-    nes.cpu.registers.pc = nes.cpu.registers.pc.wrapping_add(1);
-    if !nes.cpu.registers.status_carry() {
-        let mut pc = nes.cpu.registers.pc as i32;
-        pc = (pc + offset as i32) & 0xffff;
-        nes.cpu.registers.pc = pc as u16;
-    }
-    cycles
+pub fn bcs<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let condition = cpu.status_carry();
+    branch_if(cpu, condition, cycles)
 }
 
-pub fn bvs(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let offset = nes.get(nes.cpu.registers.pc) as i8;
-    nes.cpu.registers.pc = nes.cpu.registers.pc.wrapping_add(1);
-    if nes.cpu.registers.status_overflow() {
-        let mut pc = nes.cpu.registers.pc as i32;
-        pc = (pc + offset as i32) & 0xffff;
-        nes.cpu.registers.pc = pc as u16;
-    }
-    cycles
+pub fn bcc<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let condition = !cpu.status_carry();
+    branch_if(cpu, condition, cycles)
 }
 
-pub fn bvc(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let offset = nes.get(nes.cpu.registers.pc) as i8;
-    nes.cpu.registers.pc = nes.cpu.registers.pc.wrapping_add(1);
-    if !nes.cpu.registers.status_overflow() {
-        let mut pc = nes.cpu.registers.pc as i32;
-        pc = (pc + offset as i32) & 0xffff;
-        nes.cpu.registers.pc = pc as u16;
-    }
-    cycles
+pub fn bvs<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let condition = cpu.status_overflow();
+    branch_if(cpu, condition, cycles)
 }
 
-pub fn bmi(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let offset = nes.get(nes.cpu.registers.pc) as i8;
-    nes.cpu.registers.pc = nes.cpu.registers.pc.wrapping_add(1);
-    if nes.cpu.registers.status_negative() {
-        let mut pc = nes.cpu.registers.pc as i32;
-        pc = (pc + offset as i32) & 0xffff;
-        nes.cpu.registers.pc = pc as u16;
-    }
-    cycles
+pub fn bvc<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let condition = !cpu.status_overflow();
+    branch_if(cpu, condition, cycles)
+}
+
+pub fn bmi<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let condition = cpu.status_negative();
+    branch_if(cpu, condition, cycles)
 }
 /////////////////////////////////////////////////////////////////////////////
 // extra / extended / undocumented or unofficial opcodes
-pub fn ign(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let _ = nes.get_address_from_mode(addressing);
-    cycles
+pub fn ign<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    cycles + page_cross_penalty(cpu, addressing, address)
 }
-pub fn lax(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address);
-    nes.cpu.set_a(value);
-    nes.cpu.set_x(value);
-    nes.cpu.set_flags_from(value);
-    cycles
+pub fn lax<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let penalty = page_cross_penalty(cpu, addressing, address);
+    let value = cpu.get(address);
+    cpu.set_a(value);
+    cpu.set_x(value);
+    cpu.set_flags_from(value);
+    cycles + penalty
 }
 
-pub fn sax(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.cpu.registers.x & nes.cpu.get_a();
-    nes.set(address, value);
+pub fn sax<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get_x() & cpu.get_a();
+    cpu.set(address, value);
     cycles
 }
 
-pub fn dcp(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn dcp<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     //// dec
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address).wrapping_sub(1);
-    nes.set(address, value);
-    // nes.cpu.set_flags_from(value);
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get(address).wrapping_sub(1);
+    cpu.set(address, value);
+    // cpu.set_flags_from(value);
     //// cmp
-    // let address = nes.get_address_from_mode(addressing);
-    // let value = nes.get(address);
-    let compare = nes.cpu.registers.a as i16 - value as i16;
+    // let address = cpu.address_from_mode(addressing);
+    // let value = cpu.get(address);
+    let compare = cpu.get_a() as i16 - value as i16;
     if compare >= 0 {
-        nes.cpu.registers.set_carry();
+        cpu.set_carry();
     } else {
-        nes.cpu.registers.clear_carry();
+        cpu.clear_carry();
     }
-    nes.cpu.registers.set_flags(compare as u8);
+    cpu.set_flags_from(compare as u8);
     cycles
 }
 /// aka ISB
-pub fn isc(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn isc<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     // inc
-    let address = nes.get_address_from_mode(addressing);
-    let value = nes.get(address).wrapping_add(1);
-    nes.set(address, value);
-    nes.cpu.set_flags_from(value);
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get(address).wrapping_add(1);
+    cpu.set(address, value);
+    cpu.set_flags_from(value);
     // sbc
     let input = value as i8;
-    add_to_a(nes, input.wrapping_neg().wrapping_sub(1) as u8);
+    add_to_a(cpu, input.wrapping_neg().wrapping_sub(1) as u8);
+    cycles
+}
+
+/// nmos 6502 isc, whose subtraction honours the decimal flag
+pub fn isc_bcd<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    // inc
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get(address).wrapping_add(1);
+    cpu.set(address, value);
+    // sbc
+    if cpu.status_decimal() {
+        subtract_from_a_decimal(cpu, value);
+    } else {
+        add_to_a(cpu, !value);
+    }
     cycles
 }
 
 // ASL value then ORA
-pub fn slo(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn slo<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     let value = if addressing == 1 {
         // operate on the accumulator
-        let mut value = nes.cpu.get_a();
+        let mut value = cpu.get_a();
         if value & 1 << 7 == 1 << 7 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value <<= 1;
-        nes.cpu.set_a(value);
+        cpu.set_a(value);
         value
     } else {
         // operate per addressing mode
-        let address = nes.get_address_from_mode(addressing);
-        let mut value = nes.get(address);
+        let address = cpu.address_from_mode(addressing);
+        let mut value = cpu.get(address);
         if value & 1 << 7 == 1 << 7 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value <<= 1;
-        nes.set(address, value);
+        cpu.set(address, value);
         value
     };
-    let result = value | nes.cpu.get_a();
-    nes.cpu.registers.a = result;
-    nes.cpu.registers.set_flags(result);
+    let result = value | cpu.get_a();
+    cpu.set_a(result);
+    cpu.set_flags_from(result);
     cycles
 }
 
 /// ROL value then AND value
-pub fn rla(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn rla<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     // ROL
     let value = if addressing == 1 {
         // operate on the accumulator
-        let mut value = nes.cpu.get_a();
-        let carry = nes.cpu.registers.get_carry();
+        let mut value = cpu.get_a();
+        let carry = cpu.get_carry();
         if value & 1 << 7 == 1 << 7 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value <<= 1;
         value |= carry;
-        nes.cpu.set_a(value);
+        cpu.set_a(value);
         value
     } else {
         // operate per addressing mode
-        let address = nes.get_address_from_mode(addressing);
-        let mut value = nes.get(address);
-        let carry = nes.cpu.registers.get_carry();
+        let address = cpu.address_from_mode(addressing);
+        let mut value = cpu.get(address);
+        let carry = cpu.get_carry();
         if value & 1 << 7 == 1 << 7 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value <<= 1;
         value |= carry;
-        nes.set(address, value);
+        cpu.set(address, value);
         value
     };
     // AND
-    let result = nes.cpu.registers.a & value;
-    nes.cpu.registers.a = result;
-    nes.cpu.registers.set_flags(result);
+    let result = cpu.get_a() & value;
+    cpu.set_a(result);
+    cpu.set_flags_from(result);
     cycles
 }
 // LSR value then EOR
-pub fn sre(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn sre<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     // lsr
     let value = if addressing == 1 {
         // operate on the accumulator
-        let mut value = nes.cpu.get_a();
+        let mut value = cpu.get_a();
         if value & 0b1 == 0b1 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value >>= 1;
-        nes.cpu.set_a(value);
+        cpu.set_a(value);
         value
     } else {
         // operate per addressing mode
-        let address = nes.get_address_from_mode(addressing);
-        let mut value = nes.get(address);
+        let address = cpu.address_from_mode(addressing);
+        let mut value = cpu.get(address);
         if value & 0b1 == 0b1 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value >>= 1;
-        nes.set(address, value);
+        cpu.set(address, value);
         value
     };
     // eor
-    let result = value ^ nes.cpu.get_a();
-    nes.cpu.registers.a = result;
-    nes.cpu.registers.set_flags(result);
+    let result = value ^ cpu.get_a();
+    cpu.set_a(result);
+    cpu.set_flags_from(result);
     cycles
 }
 
 // ROR value then ADC
-pub fn rra(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+pub fn rra<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
     // ror
     let value = if addressing == 1 {
         // operate on the accumulator
-        let mut value = nes.cpu.get_a();
-        let carry = nes.cpu.registers.get_carry() << 7;
+        let mut value = cpu.get_a();
+        let carry = cpu.get_carry() << 7;
         if value & 1 == 1 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value >>= 1;
         value |= carry;
 
-        nes.cpu.set_a(value);
+        cpu.set_a(value);
         value
     } else {
         // operate per addressing mode
-        let address = nes.get_address_from_mode(addressing);
-        let mut value = nes.get(address);
-        let carry = nes.cpu.registers.get_carry() << 7;
+        let address = cpu.address_from_mode(addressing);
+        let mut value = cpu.get(address);
+        let carry = cpu.get_carry() << 7;
         if value & 1 == 1 {
-            nes.cpu.registers.set_carry();
+            cpu.set_carry();
         } else {
-            nes.cpu.registers.clear_carry();
+            cpu.clear_carry();
         }
         value >>= 1;
         value |= carry;
 
-        nes.set(address, value);
+        cpu.set(address, value);
         value
     };
-    add_to_a(nes, value);
+    add_to_a(cpu, value);
+    cycles
+}
+
+/// nmos 6502 rra, whose addition honours the decimal flag
+pub fn rra_bcd<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    // ror
+    let address = cpu.address_from_mode(addressing);
+    let mut value = cpu.get(address);
+    let carry = cpu.get_carry() << 7;
+    if value & 1 == 1 {
+        cpu.set_carry();
+    } else {
+        cpu.clear_carry();
+    }
+    value >>= 1;
+    value |= carry;
+    cpu.set(address, value);
+    // adc
+    if cpu.status_decimal() {
+        add_to_a_decimal(cpu, value);
+    } else {
+        add_to_a(cpu, value);
+    }
     cycles
 }
 
-pub fn halt(nes: &mut Nes, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    nes.cpu.registers.pc -= 1;
-    nes.cpu.running = false;
+pub fn halt<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.set_pc(cpu.get_pc().wrapping_sub(1));
+    cpu.halt();
     cycles
 }
 
 //////////////////////////////////////////////////////////////////////////////
 // unimplemented opcodes
-pub fn alr(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let _ = nes.get_address_from_mode(addressing);
+pub fn alr<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let _ = cpu.address_from_mode(addressing);
+    cycles
+}
+pub fn anc<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let _ = cpu.address_from_mode(addressing);
     cycles
 }
-pub fn anc(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let _ = nes.get_address_from_mode(addressing);
+pub fn arr<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let _ = cpu.address_from_mode(addressing);
     cycles
 }
-pub fn arr(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let _ = nes.get_address_from_mode(addressing);
+pub fn axs<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let _ = cpu.address_from_mode(addressing);
     cycles
 }
-pub fn axs(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let _ = nes.get_address_from_mode(addressing);
+
+pub fn skb<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let _ = cpu.address_from_mode(addressing);
+    cycles
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// 65c02 additions
+
+/// branch always
+pub fn bra<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let offset = cpu.get(cpu.get_pc()) as i8;
+    let pc = cpu.get_pc().wrapping_add(1);
+    let destination = pc.wrapping_add(offset as u16);
+    cpu.set_pc(destination);
+    cycles + page_crossed(pc, destination)
+}
+
+/// push x to stack
+pub fn phx<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_x();
+    cpu.stack_push(value);
+    cycles
+}
+
+/// push y to stack
+pub fn phy<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.get_y();
+    cpu.stack_push(value);
+    cycles
+}
+
+/// pull x from stack
+pub fn plx<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.stack_pop();
+    cpu.set_flags_from(value);
+    cpu.set_x(value);
     cycles
 }
 
-pub fn skb(nes: &mut Nes, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
-    let _ = nes.get_address_from_mode(addressing);
+/// pull y from stack
+pub fn ply<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let value = cpu.stack_pop();
+    cpu.set_flags_from(value);
+    cpu.set_y(value);
     cycles
 }
+
+/// store zero
+pub fn stz<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    cpu.set(address, 0);
+    cycles
+}
+
+/// test and reset bits, clears the bits of a in memory
+pub fn trb<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get(address);
+    let mask = cpu.get_a();
+    cpu.set_zero_from(value & mask);
+    cpu.set(address, value & !mask);
+    cycles
+}
+
+/// test and set bits, sets the bits of a in memory
+pub fn tsb<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get(address);
+    let mask = cpu.get_a();
+    cpu.set_zero_from(value & mask);
+    cpu.set(address, value | mask);
+    cycles
+}
+
+/// jmp with the indirect page wrapping bug fixed, also handles (a,X)
+pub fn jmp_cmos<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    let address = if addressing == 10 {
+        // Indirect
+        let pointer = cpu.get_short(cpu.get_pc());
+        let lo = cpu.get(pointer) as u16;
+        let hi = cpu.get(pointer.wrapping_add(1)) as u16;
+        (hi << 8) | lo
+    } else {
+        cpu.address_from_mode(addressing)
+    };
+    cpu.set_pc(address);
+    cycles
+}
+
+/// wait for interrupt. nothing raises interrupts on a bare core, so this stops the cpu
+pub fn wai<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.halt();
+    cycles
+}
+
+/// stop the clock until reset
+pub fn stp<P: Processor>(cpu: &mut P, _addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+    cpu.halt();
+    cycles
+}
+
+fn modify_memory_bit<P: Processor>(cpu: &mut P, addressing: u8, bit: u8, set: bool) {
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get(address);
+    if set {
+        cpu.set(address, value | (1 << bit));
+    } else {
+        cpu.set(address, value & !(1 << bit));
+    }
+}
+
+fn branch_on_memory_bit<P: Processor>(cpu: &mut P, addressing: u8, bit: u8, set: bool) {
+    let address = cpu.address_from_mode(addressing);
+    let value = cpu.get(address);
+    let offset = cpu.get(cpu.get_pc()) as i8;
+    let pc = cpu.get_pc().wrapping_add(1);
+    if (value & (1 << bit) != 0) == set {
+        cpu.set_pc(pc.wrapping_add(offset as u16));
+    } else {
+        cpu.set_pc(pc);
+    }
+}
+
+/// defines the rockwell/wdc bit instructions, which encode the bit number in the opcode
+macro_rules! bit_opcodes {
+    ($($bit:literal => $rmb:ident, $smb:ident, $bbr:ident, $bbs:ident;)*) => {
+        $(
+            /// reset memory bit
+            pub fn $rmb<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+                modify_memory_bit(cpu, addressing, $bit, false);
+                cycles
+            }
+            /// set memory bit
+            pub fn $smb<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+                modify_memory_bit(cpu, addressing, $bit, true);
+                cycles
+            }
+            /// branch on bit reset
+            pub fn $bbr<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+                branch_on_memory_bit(cpu, addressing, $bit, false);
+                cycles
+            }
+            /// branch on bit set
+            pub fn $bbs<P: Processor>(cpu: &mut P, addressing: u8, cycles: u8, _bytes: u8) -> u8 {
+                branch_on_memory_bit(cpu, addressing, $bit, true);
+                cycles
+            }
+        )*
+    };
+}
+
+bit_opcodes! {
+    0 => rmb0, smb0, bbr0, bbs0;
+    1 => rmb1, smb1, bbr1, bbs1;
+    2 => rmb2, smb2, bbr2, bbs2;
+    3 => rmb3, smb3, bbr3, bbs3;
+    4 => rmb4, smb4, bbr4, bbs4;
+    5 => rmb5, smb5, bbr5, bbs5;
+    6 => rmb6, smb6, bbr6, bbs6;
+    7 => rmb7, smb7, bbr7, bbs7;
+}
@@ -0,0 +1,121 @@
+//! turns the ppu's pixels into rgb colors.
+//! a pixel is a 6 bit palette index with the three emphasis bits above it,
+//! red in bit 6, green in bit 7 and blue in bit 8, so there are 512 colors in all
+
+use crate::ppu::FRAME_WIDTH;
+
+/// number of colors in a palette, every index with every combination of emphasis bits
+pub const PALETTE_SIZE: usize = 512;
+
+/// the emphasis bits of a pixel
+pub const EMPHASIS_RED: u16 = 1 << 6;
+pub const EMPHASIS_GREEN: u16 = 1 << 7;
+pub const EMPHASIS_BLUE: u16 = 1 << 8;
+
+/// how much an emphasis bit darkens the other two channels
+const EMPHASIS_ATTENUATION: f32 = 0.816328;
+
+/// the 64 colors of the 2C02 with no emphasis
+#[rustfmt::skip]
+const NTSC_COLORS: [[u8; 3]; 64] = [
+    [84, 84, 84], [0, 30, 116], [8, 16, 144], [48, 0, 136],
+    [68, 0, 100], [92, 0, 48], [84, 4, 0], [60, 24, 0],
+    [32, 42, 0], [8, 58, 0], [0, 64, 0], [0, 60, 0],
+    [0, 50, 60], [0, 0, 0], [0, 0, 0], [0, 0, 0],
+    [152, 150, 152], [8, 76, 196], [48, 50, 236], [92, 30, 228],
+    [136, 20, 176], [160, 20, 100], [152, 34, 32], [120, 60, 0],
+    [84, 90, 0], [40, 114, 0], [8, 124, 0], [0, 118, 40],
+    [0, 102, 120], [0, 0, 0], [0, 0, 0], [0, 0, 0],
+    [236, 238, 236], [76, 154, 236], [120, 124, 236], [176, 98, 236],
+    [228, 84, 236], [236, 88, 180], [236, 106, 100], [212, 136, 32],
+    [160, 170, 0], [116, 196, 0], [76, 208, 32], [56, 204, 108],
+    [56, 180, 204], [60, 60, 60], [0, 0, 0], [0, 0, 0],
+    [236, 238, 236], [168, 204, 236], [188, 188, 236], [212, 178, 236],
+    [236, 174, 236], [236, 174, 212], [236, 180, 176], [228, 196, 144],
+    [204, 210, 120], [180, 222, 120], [168, 226, 144], [152, 226, 180],
+    [160, 214, 228], [160, 162, 160], [0, 0, 0], [0, 0, 0],
+];
+
+/// turns a frame of pixels into rgb, 3 bytes per pixel and output_width pixels per line
+pub trait VideoFilter {
+    fn output_width(&self) -> usize;
+    /// burst_phase is the color subcarrier phase the frame started on, see Nes2c02::burst_phase
+    fn apply(&mut self, pixels: &[u16], burst_phase: u8, rgb: &mut [u8]);
+}
+
+/// a lookup from pixels to rgb
+#[derive(Clone)]
+pub struct Palette {
+    colors: Box<[[u8; 3]; PALETTE_SIZE]>,
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::ntsc()
+    }
+}
+
+impl Palette {
+    /// the built in palette
+    pub fn ntsc() -> Palette {
+        Palette::from_base_colors(&NTSC_COLORS)
+    }
+    /// loads a .pal file: 64 colors, with emphasis worked out like the hardware does,
+    /// or 512 colors which include the emphasis already. None for any other size
+    pub fn from_pal(bytes: &[u8]) -> Option<Palette> {
+        let colors: Vec<[u8; 3]> = bytes
+            .chunks_exact(3)
+            .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+            .collect();
+        if bytes.len() == 64 * 3 {
+            let mut base = [[0u8; 3]; 64];
+            base.copy_from_slice(&colors);
+            Some(Palette::from_base_colors(&base))
+        } else if bytes.len() == PALETTE_SIZE * 3 {
+            let mut palette = Palette {
+                colors: Box::new([[0u8; 3]; PALETTE_SIZE]),
+            };
+            palette.colors.copy_from_slice(&colors);
+            Some(palette)
+        } else {
+            None
+        }
+    }
+    /// fills in the emphasized colors: each emphasis bit darkens the other two channels
+    fn from_base_colors(base: &[[u8; 3]; 64]) -> Palette {
+        let mut colors = Box::new([[0u8; 3]; PALETTE_SIZE]);
+        for (pixel, color) in colors.iter_mut().enumerate() {
+            let emphasis = (pixel as u16) >> 6;
+            let mut rgb = base[pixel & 0x3f].map(|channel| channel as f32);
+            // bits 0, 1 and 2 of emphasis line up with the r, g and b channels
+            for bit in (0..3).filter(|bit| emphasis & (1 << bit) > 0) {
+                for (channel, value) in rgb.iter_mut().enumerate() {
+                    if channel != bit {
+                        *value *= EMPHASIS_ATTENUATION;
+                    }
+                }
+            }
+            *color = rgb.map(|channel| channel.round() as u8);
+        }
+        Palette { colors }
+    }
+    /// the rgb color of a pixel
+    pub fn rgb(&self, pixel: u16) -> [u8; 3] {
+        self.colors[pixel as usize % PALETTE_SIZE]
+    }
+    /// converts a whole frame of pixels to rgb, 3 bytes per pixel
+    pub fn frame_to_rgb(&self, pixels: &[u16], rgb: &mut [u8]) {
+        for (pixel, out) in pixels.iter().zip(rgb.chunks_exact_mut(3)) {
+            out.copy_from_slice(&self.rgb(*pixel));
+        }
+    }
+}
+
+impl VideoFilter for Palette {
+    fn output_width(&self) -> usize {
+        FRAME_WIDTH
+    }
+    fn apply(&mut self, pixels: &[u16], _burst_phase: u8, rgb: &mut [u8]) {
+        self.frame_to_rgb(pixels, rgb);
+    }
+}
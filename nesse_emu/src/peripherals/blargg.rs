@@ -0,0 +1,122 @@
+//! runs test roms that report with blargg's protocol: $6000 holds the status,
+//! $6001-$6003 the signature DE B0 61, and $6004 onward a zero terminated message
+use crate::prelude::*;
+
+const STATUS_ADDRESS: u16 = 0x6000;
+const SIGNATURE_ADDRESS: u16 = 0x6001;
+const MESSAGE_ADDRESS: u16 = 0x6004;
+const SIGNATURE: [u8; 3] = [0xde, 0xb0, 0x61];
+/// the test is still running
+const STATUS_RUNNING: u8 = 0x80;
+/// the test wants the reset button pressed
+const STATUS_RESET: u8 = 0x81;
+/// the protocol asks for at least 100ms before pressing reset, about 6 frames
+const RESET_DELAY_FRAMES: u32 = 6;
+/// longest message we read, in case a rom never terminates it
+const MAX_MESSAGE_LENGTH: u16 = 0x1000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TestRomResult {
+    /// the rom wrote result code 0
+    Passed(String),
+    /// the rom wrote a non-zero result code
+    Failed(u8, String),
+    /// the cpu stopped or the frame limit passed before the rom wrote a result
+    Unfinished(String),
+}
+
+impl TestRomResult {
+    pub fn passed(&self) -> bool {
+        matches!(self, TestRomResult::Passed(_))
+    }
+    pub fn message(&self) -> &str {
+        match self {
+            TestRomResult::Passed(message) => message,
+            TestRomResult::Failed(_, message) => message,
+            TestRomResult::Unfinished(message) => message,
+        }
+    }
+}
+
+/// watches the result protocol once per frame, pressing reset when asked
+/// and stopping the nes once a result is written
+pub struct BlarggRunner {
+    frames: u32,
+    max_frames: u32,
+    reset_countdown: Option<u32>,
+    result: Option<TestRomResult>,
+}
+
+impl BlarggRunner {
+    pub fn new(max_frames: u32) -> BlarggRunner {
+        BlarggRunner {
+            frames: 0,
+            max_frames,
+            reset_countdown: None,
+            result: None,
+        }
+    }
+    /// the result written by the rom, if it finished
+    pub fn result(&self) -> Option<&TestRomResult> {
+        self.result.as_ref()
+    }
+    /// number of frames run so far
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+    fn message(nes: &mut Nes) -> String {
+        let mut message = vec![];
+        for offset in 0..MAX_MESSAGE_LENGTH {
+            let value = nes.get(MESSAGE_ADDRESS + offset);
+            if value == 0 {
+                break;
+            }
+            message.push(value);
+        }
+        String::from_utf8_lossy(&message).trim_end().to_string()
+    }
+}
+
+impl NesPeripheral for BlarggRunner {
+    fn on_vblank(&mut self, nes: &mut Nes) {
+        self.frames += 1;
+        if nes.get_region(SIGNATURE_ADDRESS, 3) == SIGNATURE {
+            match nes.get(STATUS_ADDRESS) {
+                STATUS_RUNNING => {}
+                STATUS_RESET => match self.reset_countdown {
+                    None => self.reset_countdown = Some(RESET_DELAY_FRAMES),
+                    Some(0) => {
+                        self.reset_countdown = None;
+                        nes.reset();
+                    }
+                    Some(frames) => self.reset_countdown = Some(frames - 1),
+                },
+                0 => {
+                    self.result = Some(TestRomResult::Passed(Self::message(nes)));
+                    nes.cpu.running = false;
+                }
+                code => {
+                    self.result = Some(TestRomResult::Failed(code, Self::message(nes)));
+                    nes.cpu.running = false;
+                }
+            }
+        }
+        if self.result.is_none() && self.frames >= self.max_frames {
+            nes.cpu.running = false;
+        }
+    }
+}
+
+/// runs a test rom headless until it reports a result, stops, or max_frames pass.
+/// returns None if the rom can't be loaded
+pub fn run_test_rom(rom: &[u8], max_frames: u32) -> Option<TestRomResult> {
+    let cartridge = NesCart::from_slice(rom)?;
+    let mut runner = BlarggRunner::new(max_frames);
+    let mut nes = Nes::default().with_peripheral(&mut runner);
+    nes.insert_cartridge(cartridge);
+    nes.init();
+    nes.master_clock_drive();
+    let message = BlarggRunner::message(&mut nes);
+    drop(nes);
+    Some(runner.result.unwrap_or(TestRomResult::Unfinished(message)))
+}
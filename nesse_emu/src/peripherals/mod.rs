@@ -1,4 +1,7 @@
 use crate::prelude::*;
+
+pub mod blargg;
+
 /// allows a function to be called by an instance of the NES at each tick
 pub trait NesPeripheral {
     /// run on nes init
@@ -21,261 +24,272 @@ impl Spy {
 
 impl NesPeripheral for Spy {
     fn tick(&mut self, nes: &mut Nes) {
-        let regs = nes.dump_registers();
-        let pc = regs.get_pc();
-        let mut line = String::new();
+        let pc = nes.cpu.get_pc();
         if let Some((_, label)) = self.0.iter().find(|(address, _)| *address == pc) {
-            line.push_str(&format!("{}:\n", label));
-            // len += label.len() + 2;
-            // println!("LABEL {} at {:x}", label, pc);
+            println!("{}:", label);
         }
+        println!("{}", trace_line(nes));
+    }
+}
 
-        line.push_str(&format!("{:04X}  ", pc));
-        let opcode = nes.peek_pc();
-        let opcode_stats = &OPCODE_JUMPTABLE[opcode as usize];
-        let bytes = opcode_stats.bytes as u16;
-        let mode = opcode_stats.addressing;
-        let mut dissassembled: [u8; 3] = [opcode, 0, 0];
-        for byte in 1u16..bytes {
-            let digit = nes.get(byte + pc);
-            dissassembled[byte as usize] = digit;
-        }
-        for i in 0..3 {
-            if i < bytes {
-                line.push_str(&format!("{:02X} ", dissassembled[i as usize]));
-            } else {
-                line.push_str("   ");
-            }
-        }
-        let (_, name) = OPCODE_NAMES[opcode as usize];
-        if name.len() == 3 {
-            line.push_str(&format!(" {} ", name));
+/// reads memory for the trace without touching the io registers, since reading those has side effects.
+/// they show up as $FF, as in nintendulator's logs
+fn peek(nes: &mut Nes, address: u16) -> u8 {
+    if (0x2000..0x4020).contains(&address) {
+        0xff
+    } else {
+        nes.get(address)
+    }
+}
+
+fn peek_short(nes: &mut Nes, address: u16) -> u16 {
+    let low = peek(nes, address) as u16;
+    let high = peek(nes, address.wrapping_add(1)) as u16;
+    high << 8 | low
+}
+
+/// formats the instruction about to run in the style of nestest.log
+pub fn trace_line(nes: &mut Nes) -> String {
+    let regs = nes.dump_registers();
+    let pc = regs.get_pc();
+    let mut line = String::new();
+    line.push_str(&format!("{:04X}  ", pc));
+    let opcode = nes.peek_pc();
+    let opcode_stats = &nes.cpu.variant.jumptable::<Nes>()[opcode as usize];
+    let bytes = opcode_stats.bytes as u16;
+    let mode = opcode_stats.addressing;
+    let mut dissassembled: [u8; 3] = [opcode, 0, 0];
+    for byte in 1u16..bytes {
+        let digit = peek(nes, byte + pc);
+        dissassembled[byte as usize] = digit;
+    }
+    for i in 0..3 {
+        if i < bytes {
+            line.push_str(&format!("{:02X} ", dissassembled[i as usize]));
         } else {
-            // the extra opcodes have a * in front of the name
-            line.push_str(&format!("{} ", name));
+            line.push_str("   ");
         }
+    }
+    let (_, name) = OPCODE_NAMES[opcode as usize];
+    if name.len() == 3 {
+        line.push_str(&format!(" {} ", name));
+    } else {
+        // the extra opcodes have a * in front of the name
+        line.push_str(&format!("{} ", name));
+    }
 
-        let len;
-        match mode {
-            0 => {
-                // Implicit
-                // line.push_str("imp ");
-                len = 0;
-            }
-            1 => {
-                // Accumulator
-                line.push('A');
-                len = 1;
-            }
-            2 => {
-                // Immediate
-                let value = nes.get(pc + 1);
-                line.push_str(&format!("#${:02X}", value));
-                len = 4;
-            }
-            3 => {
-                // ZeroPage
-                let address = nes.get(pc + 1);
-                let value = nes.get(address as u16);
-                line.push_str(&format!("${:02X} = {:02X}", address, value));
-                len = 8;
-            }
-            4 => {
-                // ZeroPageX
-                // immediate value, resulant total, value at that address
-                // "$33,X @ 33 = AA"
-                let offset = nes.get(pc + 1);
-                let total = offset.wrapping_add(regs.x);
-                let value = nes.get(total as u16);
-                line.push_str(&format!(
-                    "${:02X},X @ {:02X} = {:02X}",
-                    offset, total, value
-                ));
-                len = 15;
-            }
-            5 => {
-                // ZeroPageY
-                // line.push_str("zpy ");
-                let offset = nes.get(pc + 1);
-                let total = offset.wrapping_add(regs.y);
-                let value = nes.get(total as u16);
-                line.push_str(&format!(
-                    "${:02X},Y @ {:02X} = {:02X}",
-                    offset, total, value
-                ));
-                len = 15;
-            }
-            6 => {
-                // Relative
-                let value = nes.get(pc + 1) as i8; // adding one since pc hasn't been incremented past opcode at this point
-                let destination = ((regs.pc as i32 + 2).wrapping_add(value as i32) & 0xffff) as u16;
-                line.push_str(&format!("${:04X}", destination));
+    let len;
+    match mode {
+        0 => {
+            // Implicit
+            // line.push_str("imp ");
+            len = 0;
+        }
+        1 => {
+            // Accumulator
+            line.push('A');
+            len = 1;
+        }
+        2 => {
+            // Immediate
+            let value = peek(nes, pc + 1);
+            line.push_str(&format!("#${:02X}", value));
+            len = 4;
+        }
+        3 => {
+            // ZeroPage
+            let address = peek(nes, pc + 1);
+            let value = peek(nes, address as u16);
+            line.push_str(&format!("${:02X} = {:02X}", address, value));
+            len = 8;
+        }
+        4 => {
+            // ZeroPageX
+            // immediate value, resulant total, value at that address
+            // "$33,X @ 33 = AA"
+            let offset = peek(nes, pc + 1);
+            let total = offset.wrapping_add(regs.x);
+            let value = peek(nes, total as u16);
+            line.push_str(&format!(
+                "${:02X},X @ {:02X} = {:02X}",
+                offset, total, value
+            ));
+            len = 15;
+        }
+        5 => {
+            // ZeroPageY
+            // line.push_str("zpy ");
+            let offset = peek(nes, pc + 1);
+            let total = offset.wrapping_add(regs.y);
+            let value = peek(nes, total as u16);
+            line.push_str(&format!(
+                "${:02X},Y @ {:02X} = {:02X}",
+                offset, total, value
+            ));
+            len = 15;
+        }
+        6 => {
+            // Relative
+            let value = peek(nes, pc + 1) as i8; // adding one since pc hasn't been incremented past opcode at this point
+            let destination = ((regs.pc as i32 + 2).wrapping_add(value as i32) & 0xffff) as u16;
+            line.push_str(&format!("${:04X}", destination));
+            len = 5;
+        }
+        7 => {
+            // Absolute
+            // todo: this is a real mess. find a better way to discriminate between different opcodes that load and store memory values
+            let value = peek_short(nes, pc + 1); // adding one since pc hasn't been incremented past opcode at this point
+            let dereferenced = peek(nes, value);
+            if opcode == 0x32 || opcode == 0x4C || opcode == 0x20 {
+                // jsr doesn't print value at address
+                line.push_str(&format!("${:04X}", value));
                 len = 5;
+            } else {
+                line.push_str(&format!("${:04X} = {:02X}", value, dereferenced));
+                len = 10;
             }
-            7 => {
-                // Absolute
-                // todo: this is a real mess. find a better way to discriminate between different opcodes that load and store memory values
-                let value = nes.get_short(pc + 1); // adding one since pc hasn't been incremented past opcode at this point
-                let dereferenced = nes.get(value);
-                if opcode == 0x32 || opcode == 0x4C || opcode == 0x20 {
-                    // jsr doesn't print value at address
-                    line.push_str(&format!("${:04X}", value));
-                    len = 5;
-                } else {
-                    line.push_str(&format!("${:04X} = {:02X}", value, dereferenced));
-                    len = 10;
-                }
-            }
-            8 => {
-                // AbsoluteX
-                // $0633,X @ 0633 = AA
-                let value = nes.get_short(pc + 1); // adding one since pc hasn't been incremented past opcode at this point
-                let total = value.wrapping_add(nes.cpu.get_x() as u16);
-                let dereferenced = nes.get(total);
-                line.push_str(&format!(
-                    "${:04X},X @ {:04X} = {:02X}",
-                    value, total, dereferenced
-                ));
-                len = 19;
-            }
-            9 => {
-                // AbsoluteY
-                let value = nes.get_short(pc + 1); // adding one since pc hasn't been incremented past opcode at this point
-                let total = value.wrapping_add(nes.cpu.get_y() as u16);
-                let dereferenced = nes.get(total);
-                use OpGroup::*;
-                // todo: fix this mess
-                match opcode_group(opcode) {
-                    Control => {
-                        let hi = opcode & 0xf0;
-                        let lo = opcode & 0xf;
-                        let high_pass = hi == 0xa0;
-                        let low_pass =
-                            lo == 0x0c && (hi == 0x80 || hi == 0x20 || hi == 0xe0 || hi == 0xc0);
-                        if high_pass || low_pass {
-                            line.push_str(&format!(
-                                "${:04X},Y @ {:04X} = {:02X}",
-                                value, total, dereferenced
-                            ));
-                            len = 19;
-                        } else {
-                            line.push_str(&format!("${:04X},Y", value));
-                            len = 14;
-                        }
-                    }
-                    Alu => {
-                        line.push_str(&format!(
-                            "${:04X},Y @ {:04X} = {:02X}",
-                            value, total, dereferenced
-                        ));
-                        len = 19;
-                    }
-                    Rmw => {
-                        line.push_str(&format!(
-                            "${:04X},Y @ {:04X} = {:02X}",
-                            value, total, dereferenced
-                        ));
-                        len = 19;
-                    }
-                    Unoff => {
+        }
+        8 => {
+            // AbsoluteX
+            // $0633,X @ 0633 = AA
+            let value = peek_short(nes, pc + 1); // adding one since pc hasn't been incremented past opcode at this point
+            let total = value.wrapping_add(nes.cpu.get_x() as u16);
+            let dereferenced = peek(nes, total);
+            line.push_str(&format!(
+                "${:04X},X @ {:04X} = {:02X}",
+                value, total, dereferenced
+            ));
+            len = 19;
+        }
+        9 => {
+            // AbsoluteY
+            let value = peek_short(nes, pc + 1); // adding one since pc hasn't been incremented past opcode at this point
+            let total = value.wrapping_add(nes.cpu.get_y() as u16);
+            let dereferenced = peek(nes, total);
+            use OpGroup::*;
+            // todo: fix this mess
+            match opcode_group(opcode) {
+                Control => {
+                    let hi = opcode & 0xf0;
+                    let lo = opcode & 0xf;
+                    let high_pass = hi == 0xa0;
+                    let low_pass =
+                        lo == 0x0c && (hi == 0x80 || hi == 0x20 || hi == 0xe0 || hi == 0xc0);
+                    if high_pass || low_pass {
                         line.push_str(&format!(
                             "${:04X},Y @ {:04X} = {:02X}",
                             value, total, dereferenced
                         ));
                         len = 19;
+                    } else {
+                        line.push_str(&format!("${:04X},Y", value));
+                        len = 14;
                     }
                 }
+                Alu => {
+                    line.push_str(&format!(
+                        "${:04X},Y @ {:04X} = {:02X}",
+                        value, total, dereferenced
+                    ));
+                    len = 19;
+                }
+                Rmw => {
+                    line.push_str(&format!(
+                        "${:04X},Y @ {:04X} = {:02X}",
+                        value, total, dereferenced
+                    ));
+                    len = 19;
+                }
+                Unoff => {
+                    line.push_str(&format!(
+                        "${:04X},Y @ {:04X} = {:02X}",
+                        value, total, dereferenced
+                    ));
+                    len = 19;
+                }
             }
-            10 => {
-                // Indirect
-
-                let address = nes.get_short(nes.cpu.get_pc() + 1);
+        }
+        10 => {
+            // Indirect
 
-                let address_lo = (address & 0xff) as u8;
-                let address_hi = address & 0xff00;
-                let lo = nes.get(address) as u16;
-                let hi_address = address_lo.wrapping_add(1) as u16 | address_hi;
-                let hi = nes.get(hi_address) as u16;
-                let deref = (hi << 8) | lo;
+            let address = peek_short(nes, nes.cpu.get_pc() + 1);
 
-                line.push_str(&format!("(${:04X}) = {:04X}", address, deref));
-                len = 14;
-            }
-            11 => {
-                // IndexedIndirect (indirect x)
-                // "($80,X) @ 80 = 0200 = 5A"
-                let table = nes.get(pc + 1);
-                let base = table.wrapping_add(regs.x);
-                // let value = nes.get(address as u16);
-                let lo = nes.get(base as u16) as u16;
-                let hi = nes.get(base.wrapping_add(1) as u16) as u16;
-                let address = hi << 8 | lo;
-                let value = nes.get(address);
-                line.push_str(&format!(
-                    "(${:02X},X) @ {:02X} = {:04X} = {:02X}",
-                    table, base, address, value
-                ));
-                len = 24;
-            }
-            12 => {
-                // IndirectIndexed (indirect y)
-                // immediate, short value in zp, total plus y, value at that address
-                // "($33),Y = 0400 @ 0400 = 7F"
-                // let address = nes.get(pc+1);
-                // let value = nes.get(address);
-                let immediate = nes.get(pc + 1);
-                let lo = nes.get(immediate as u16) as u16;
-                let hi = nes.get(immediate.wrapping_add(1) as u16) as u16; // wraps around zero page
-                let short = hi << 8 | lo;
-                let address = short.wrapping_add(regs.y as u16);
-                let value = nes.get(address);
+            let address_lo = (address & 0xff) as u8;
+            let address_hi = address & 0xff00;
+            let lo = peek(nes, address) as u16;
+            let hi_address = address_lo.wrapping_add(1) as u16 | address_hi;
+            let hi = peek(nes, hi_address) as u16;
+            let deref = (hi << 8) | lo;
 
-                line.push_str(&format!(
-                    "(${:02X}),Y = {:04X} @ {:04X} = {:02X}",
-                    immediate, short, address, value
-                ));
-                len = 26;
-            }
-            _ => {
-                unimplemented!()
-            }
+            line.push_str(&format!("(${:04X}) = {:04X}", address, deref));
+            len = 14;
         }
-        // if let Some((_, label)) = LABEL_LIST.iter().find(|(address, _)| *address == pc) {
-        //     line.push_str(&format!("({})", label));
-        //     len += label.len() + 2;
-        //     // println!("LABEL {} at {:x}", label, pc);
-        // }
-        if len > 28 {
-            // panic!("what?");
-        } else {
-            let spaces = 28 - len;
-            for _ in 0..spaces {
-                line.push(' ');
-            }
+        11 => {
+            // IndexedIndirect (indirect x)
+            // "($80,X) @ 80 = 0200 = 5A"
+            let table = peek(nes, pc + 1);
+            let base = table.wrapping_add(regs.x);
+            // let value = peek(nes, address as u16);
+            let lo = peek(nes, base as u16) as u16;
+            let hi = peek(nes, base.wrapping_add(1) as u16) as u16;
+            let address = hi << 8 | lo;
+            let value = peek(nes, address);
+            line.push_str(&format!(
+                "(${:02X},X) @ {:02X} = {:04X} = {:02X}",
+                table, base, address, value
+            ));
+            len = 24;
         }
-        line.push_str(&format!("A:{:02X} ", regs.a));
-        line.push_str(&format!("X:{:02X} ", regs.x));
-        line.push_str(&format!("Y:{:02X} ", regs.y));
-        line.push_str(&format!("P:{:02X} ", regs.p));
-        line.push_str(&format!("SP:{:02X} ", regs.sp));
+        12 => {
+            // IndirectIndexed (indirect y)
+            // immediate, short value in zp, total plus y, value at that address
+            // "($33),Y = 0400 @ 0400 = 7F"
+            // let address = peek(nes, pc+1);
+            // let value = peek(nes, address);
+            let immediate = peek(nes, pc + 1);
+            let lo = peek(nes, immediate as u16) as u16;
+            let hi = peek(nes, immediate.wrapping_add(1) as u16) as u16; // wraps around zero page
+            let short = hi << 8 | lo;
+            let address = short.wrapping_add(regs.y as u16);
+            let value = peek(nes, address);
 
-        let ppu_cycle = 10_055;
-        {
-            let ppu_thousands = ppu_cycle / 1000;
-            let ppu_hundreds = ppu_cycle % 1000;
-            line.push_str("PPU:");
-            line.push_str(&format!("{:>3},{:>3} ", ppu_thousands, ppu_hundreds));
+            line.push_str(&format!(
+                "(${:02X}),Y = {:04X} @ {:04X} = {:02X}",
+                immediate, short, address, value
+            ));
+            len = 26;
+        }
+        _ => {
+            unimplemented!()
         }
-        let cpu_cycle = nes.cpu.get_cycles();
-        line.push_str(&format!("CYC:{}", cpu_cycle));
+    }
+    // if let Some((_, label)) = LABEL_LIST.iter().find(|(address, _)| *address == pc) {
+    //     line.push_str(&format!("({})", label));
+    //     len += label.len() + 2;
+    //     // println!("LABEL {} at {:x}", label, pc);
+    // }
+    if len > 28 {
+        // panic!("what?");
+    } else {
+        let spaces = 28 - len;
+        for _ in 0..spaces {
+            line.push(' ');
+        }
+    }
+    line.push_str(&format!("A:{:02X} ", regs.a));
+    line.push_str(&format!("X:{:02X} ", regs.x));
+    line.push_str(&format!("Y:{:02X} ", regs.y));
+    line.push_str(&format!("P:{:02X} ", regs.p));
+    line.push_str(&format!("SP:{:02X} ", regs.sp));
 
-        let _stack = nes.dump_stack();
-        // let pc = regs.get_pc();
-        // print!("{:2x} ## {:?} ", next_opcode, regs);
+    line.push_str(&format!("PPU:{:>3},{:>3} ", nes.ppu.scanline, nes.ppu.dot));
+    let cpu_cycle = nes.cpu.get_cycles();
+    line.push_str(&format!("CYC:{}", cpu_cycle));
 
-        println!("{}", line);
-        // println!("{}", stack);
-    }
+    // let _stack = nes.dump_stack();
+    // let pc = regs.get_pc();
+    // print!("{:2x} ## {:?} ", next_opcode, regs);
+    line
 }
 
 enum OpGroup {
@@ -1,52 +1,181 @@
+use crate::palette::{EMPHASIS_BLUE, EMPHASIS_GREEN, EMPHASIS_RED};
 use crate::prelude::*;
+use crate::video::Frame;
+use std::time::Duration;
+
+mod debug_view;
+pub use debug_view::{OamEntry, RgbaImage};
+
 /// nes ppu instance
 pub struct Nes2c02 {
     /// the read/write bus current value
     latch: DecayingLatch,
+    /// how long a bit of the latch holds its value once nothing drives it,
+    /// somewhere around 600ms on real consoles
+    pub open_bus_decay: Duration,
+    /// ppu ticks since power on, the clock the latch decays by
+    ticks: u64,
     /// the internal status of the control register
     control: ControlRegister,
     /// silly, but some behaviour is temperture dependant
     temp: Temperature,
-    /// are we in v_blank right now
-    v_blank: bool,
     mask: MaskRegister,
     status: StatusRegister,
     pub oam_address: u8,
-    pub oam_data: u8,
-    pub scroll: u8,
-    pub address: AddressRegisterLatch,
+    /// the $2007 read buffer, reads return what the previous read fetched
     pub data: u8,
     pub oam_dma: u8,
     // 0, 1, 2, or 3 to indicate syncronization between cpu and ppu clocks
     pub timing: u8,
     pub clock_counter: u8,
-    pub frame_clock: u16,
+    /// the nmi output, vblank and the $2000 enable bit together
+    nmi_output: bool,
+    /// set when the nmi output goes high, until the cpu takes it
+    nmi_pending: bool,
+    /// set when vblank starts, until the nes hands the frame to its peripherals
+    frame_complete: bool,
+    /// set when the last pixel of a visible scanline is drawn, until the nes hands it out
+    scanline_complete: bool,
+    /// the dot within the current scanline, 0 to 340
+    pub dot: u16,
+    /// the current scanline, -1 to 260: the pre-render line, 240 visible lines,
+    /// an idle line, then vblank from 241. pal and dendy go up to 310
+    pub scanline: i16,
+    /// decides the number of scanlines and when vblank starts, set with Nes::set_console_region
+    pub region: Region,
     pub pallete_table: [u8; 32],
     pub vram: [u8; 2048],
     pub oam: [u8; 256],
+    /// the current vram address, which doubles as the scroll position while rendering.
+    /// "v" in loopy's notation: 0yyy NNYY YYYX XXXX
+    pub vram_address: u16,
+    /// the scroll position of the top left of the screen, copied into vram_address while rendering.
+    /// "t" in loopy's notation
+    pub temp_address: u16,
+    /// the scroll position within a tile, 0 to 7
+    pub fine_x: u8,
+    /// which half of a $2005 or $2006 write comes next, shared by both.
+    /// "w" in loopy's notation, cleared by reading $2002
+    pub write_toggle: bool,
+    /// background tiles fetched ahead of the pixels they become
+    background: BackgroundFetch,
+    /// the sprites on the current scanline, found while drawing the previous one
+    sprites: [SpriteFetch; 64],
+    /// how many entries of sprites are in use
+    sprite_count: usize,
+    /// draw every sprite on a scanline instead of the first 8, to avoid flicker.
+    /// the overflow flag still behaves as if the limit was in place
+    pub unlimited_sprites: bool,
+    /// the picture as palette indices with emphasis bits, 256 by 240
+    framebuffer: Box<[u16; FRAME_WIDTH * FRAME_HEIGHT]>,
+    /// number of frames completed
+    pub frame: u64,
+    /// the phase of the color subcarrier, 0 to 11, which moves 8 steps every dot
+    signal_phase: u8,
+    /// signal_phase as the first pixel of the frame was drawn
+    burst_phase: u8,
+}
+
+pub const FRAME_WIDTH: usize = 256;
+pub const FRAME_HEIGHT: usize = 240;
+
+/// the tile data of the next two background tiles
+#[derive(Default, Clone, Copy)]
+struct BackgroundFetch {
+    nametable: u8,
+    attribute: u8,
+    pattern_lo: u8,
+    pattern_hi: u8,
+    /// pattern bits for the pixels being drawn, the high byte is the current tile
+    pattern_lo_shift: u16,
+    pattern_hi_shift: u16,
+    /// palette bits, expanded to one per pixel like the pattern bits
+    attribute_lo_shift: u16,
+    attribute_hi_shift: u16,
+}
+
+/// how many sprites the hardware draws on one scanline
+pub const SPRITES_PER_LINE: usize = 8;
+
+/// a sprite found on the current scanline
+#[derive(Default, Clone, Copy)]
+struct SpriteFetch {
+    x: u8,
+    /// palette in the low two bits, then priority and flipping
+    attributes: u8,
+    /// the row of the pattern for this scanline, already flipped
+    pattern_lo: u8,
+    pattern_hi: u8,
+    /// sprite 0 is the one tested for sprite 0 hit
+    sprite_zero: bool,
+}
+
+impl SpriteFetch {
+    /// the sprite's pixel value at x, 0 when transparent or not covering x
+    fn pixel(&self, x: usize) -> u8 {
+        let column = x.wrapping_sub(self.x as usize);
+        if column >= 8 {
+            return 0;
+        }
+        let bit = 0x80 >> column;
+        ((self.pattern_hi & bit > 0) as u8) << 1 | (self.pattern_lo & bit > 0) as u8
+    }
+    fn palette(&self) -> u8 {
+        self.attributes & 0b11
+    }
+    /// whether the background is drawn over this sprite
+    fn behind_background(&self) -> bool {
+        self.attributes & 0b0010_0000 > 0
+    }
 }
 
+impl BackgroundFetch {
+    /// moves the fetched tile into the low byte of the shift registers
+    fn load(&mut self) {
+        self.pattern_lo_shift = (self.pattern_lo_shift & 0xff00) | self.pattern_lo as u16;
+        self.pattern_hi_shift = (self.pattern_hi_shift & 0xff00) | self.pattern_hi as u16;
+        // each attribute bit fills a whole byte, so it lasts the 8 pixels of the tile
+        let lo = 0xff * (self.attribute & 1) as u16;
+        let hi = 0xff * ((self.attribute >> 1) & 1) as u16;
+        self.attribute_lo_shift = (self.attribute_lo_shift & 0xff00) | lo;
+        self.attribute_hi_shift = (self.attribute_hi_shift & 0xff00) | hi;
+    }
+    fn shift(&mut self) {
+        self.pattern_lo_shift <<= 1;
+        self.pattern_hi_shift <<= 1;
+        self.attribute_lo_shift <<= 1;
+        self.attribute_hi_shift <<= 1;
+    }
+    /// the palette and pixel value at the fine x position
+    fn pixel(&self, fine_x: u8) -> (u8, u8) {
+        let bit = 0x8000 >> fine_x;
+        let pixel = ((self.pattern_hi_shift & bit > 0) as u8) << 1
+            | (self.pattern_lo_shift & bit > 0) as u8;
+        let palette = ((self.attribute_hi_shift & bit > 0) as u8) << 1
+            | (self.attribute_lo_shift & bit > 0) as u8;
+        (palette, pixel)
+    }
+}
 
-/// this is really silly but I am adding it anyway
-/// will probably delete later
+/// after power on or reset the ppu ignores writes to $2000, $2001, $2005 and $2006
+/// until it has been running for region.ppu_warm_up_cycles() cpu cycles
 enum Temperature {
+    /// master clock ticks since power on or reset
     Cold(u32),
     Warm,
 }
 
 impl Temperature {
-    fn tick(&mut self) {
+    fn tick(&mut self, region: Region) {
         use Temperature::*;
-        match self {
-            Temperature::Cold(count) => {
-                *self = if *count > 30_000 {
-                    Warm
-                } else {
-                    // todo: this might need to be incremented more than 1 per tick
-                    Cold(*count + 1)
-                }
-            },
-            Warm => todo!(),
+        if let Cold(elapsed) = self {
+            let elapsed = *elapsed + region.ppu_divider() as u32;
+            let warm_up = region.ppu_warm_up_cycles() * region.cpu_divider() as u32;
+            *self = if elapsed >= warm_up {
+                Warm
+            } else {
+                Cold(elapsed)
+            };
         }
     }
     fn is_warm(&self) -> bool {
@@ -58,33 +187,32 @@ impl Temperature {
     }
 }
 
-
-#[derive(Copy, Clone)]
-enum DecayingLatch {
-    Decaying(u8),
-    Stable(u8, u8),
+/// the i/o latch, which holds the last value on the data bus between the cpu and the ppu.
+/// each bit fades back to 0 on its own once nothing has driven it for a while
+#[derive(Copy, Clone, Default)]
+struct DecayingLatch {
+    value: u8,
+    /// the ppu tick each bit was last driven on
+    refreshed: [u64; 8],
 }
 
 impl DecayingLatch {
-    fn read(&self) -> u8 {
-        match self {
-            DecayingLatch::Decaying(l) => *l,
-            DecayingLatch::Stable(l, _) => *l,
+    /// the latch at ppu tick now, bits driven more than decay ticks ago read as 0
+    fn read(&self, now: u64, decay: u64) -> u8 {
+        let mut value = self.value;
+        for (bit, refreshed) in self.refreshed.iter().enumerate() {
+            if now.saturating_sub(*refreshed) >= decay {
+                value &= !(1 << bit);
+            }
         }
+        value
     }
-    fn write(&mut self, value: u8) {
-        *self = DecayingLatch::Stable(value, 3);
-    }
-    fn decay(&mut self) {
-        use DecayingLatch::*;
-        *self = match self {
-            Decaying(l) => Decaying(*l),
-            Stable(l, counter) => {
-                if *counter > 0 {
-                    Stable(*l, *counter - 1)
-                } else {
-                    Decaying(*l)
-                }
+    /// drives the bits set in mask to those of value
+    fn write(&mut self, value: u8, mask: u8, now: u64) {
+        self.value = (self.value & !mask) | (value & mask);
+        for (bit, refreshed) in self.refreshed.iter_mut().enumerate() {
+            if mask & (1 << bit) > 0 {
+                *refreshed = now;
             }
         }
     }
@@ -97,45 +225,422 @@ pub struct PpuBus<'a, 'b> {
 
 impl<'a, 'b> Bus for PpuBus<'a, 'b> {
     fn bounds(&self) -> (u16, u16) {
-        todo!()
+        (0, 0x4000)
     }
 
     fn set(&mut self, address: u16, value: u8) {
-        todo!()
+        let address = address & 0x3fff;
+        if address < 0x2000 {
+            // pattern tables, ignored unless the cartridge has chr ram
+            self.cart.set_chr(address, value);
+        } else if address < 0x3f00 {
+            *self.nametable(address) = value;
+        } else {
+            self.ppu.pallete_table[palette_offset(address)] = value & 0x3f;
+        }
     }
 
     fn get(&mut self, address: u16) -> u8 {
-        todo!()
+        let address = address & 0x3fff;
+        if address < 0x2000 {
+            // pattern tables
+            self.cart.get_chr(address)
+        } else if address < 0x3f00 {
+            *self.nametable(address)
+        } else {
+            self.ppu.pallete_table[palette_offset(address)]
+        }
     }
 }
 
-impl Nes2c02 {
-    pub fn ppu_bus<'a, 'b>(&'a mut self, cart: &'b mut NesCart) -> PpuBus<'a, 'b> {
-        PpuBus { ppu: self, cart }
+impl<'a, 'b> PpuBus<'a, 'b> {
+    /// the nametable byte at an address in $2000-$3EFF, $3000-$3EFF mirrors $2000-$2EFF.
+    /// the ppu has 2kB of its own, four screen cartridges bring the other 2kB
+    fn nametable(&mut self, address: u16) -> &mut u8 {
+        let offset = self.cart.mirroring().nametable_offset(address);
+        if offset < self.ppu.vram.len() {
+            &mut self.ppu.vram[offset]
+        } else {
+            &mut self.cart.nametable_ram()[offset - self.ppu.vram.len()]
+        }
     }
 }
 
-pub enum AddressRegisterLatch {
-    Unset,
-    First(u8),
-    Second(u8, u8),
+/// the offset into palette ram of an address in $3F00-$3FFF.
+/// $3F10/$3F14/$3F18/$3F1C mirror $3F00/$3F04/$3F08/$3F0C
+fn palette_offset(address: u16) -> usize {
+    let offset = address & 0x1f;
+    if offset & 0b10011 == 0b10000 {
+        (offset & 0x0f) as usize
+    } else {
+        offset as usize
+    }
 }
 
-enum DataRegisterLatch {
-    Stale(u8),
-    Fresh(u8),
+impl Nes2c02 {
+    pub fn ppu_bus<'a, 'b>(&'a mut self, cart: &'b mut NesCart) -> PpuBus<'a, 'b> {
+        PpuBus { ppu: self, cart }
+    }
 }
 
 impl Nes2c02 {
-    pub fn tick(&mut self, _cart: &mut Option<NesCart>) {
-        
-        self.temp.tick();
-        // tick
-
-        self.latch.decay();
+    pub fn tick(&mut self, cart: &mut Option<NesCart>) {
+        self.temp.tick(self.region);
+        self.ticks += 1;
+        let rendering_enabled = self.mask.display_bg() || self.mask.display_fg();
+        let visible = (0..FRAME_HEIGHT as i16).contains(&self.scanline);
+        if rendering_enabled {
+            self.render_tick(cart);
+        } else if visible && (1..257).contains(&self.dot) {
+            // with rendering off the backdrop color is shown, unless vram_address points
+            // into the palettes, then that entry is
+            let address = if self.vram_address & 0x3f00 == 0x3f00 {
+                self.vram_address
+            } else {
+                0x3f00
+            };
+            let color = self.pallete_table[palette_offset(address)] & self.mask.palette_mask();
+            let offset = self.scanline as usize * FRAME_WIDTH + self.dot as usize - 1;
+            self.framebuffer[offset] = color as u16 | self.mask.emphasis(self.region);
+        }
+        if visible && self.dot == 257 {
+            self.scanline_complete = true;
+        }
+        if self.dot == 1 {
+            if self.scanline == self.region.vblank_scanline() {
+                self.status.set_vblank();
+                self.frame_complete = true;
+                self.update_nmi();
+            } else if self.scanline == PRERENDER_SCANLINE {
+                self.status.clear_vblank();
+                self.status.clear_sprite_flags();
+                self.update_nmi();
+            }
+        }
+        if (self.scanline, self.dot) == (0, 1) {
+            self.burst_phase = self.signal_phase;
+        }
+        self.signal_phase = (self.signal_phase + 8) % 12;
+        // with rendering on, odd ntsc frames skip the last dot of the pre-render line
+        let skip = rendering_enabled
+            && self.scanline == PRERENDER_SCANLINE
+            && self.frame & 1 == 1
+            && self.region.skips_odd_frame_dot();
+        let last_dot = if skip { LAST_DOT - 1 } else { LAST_DOT };
+        self.dot += 1;
+        if self.dot > last_dot {
+            self.dot = 0;
+            self.scanline += 1;
+            if self.scanline > self.region.last_scanline() {
+                self.scanline = PRERENDER_SCANLINE;
+                self.frame += 1;
+            }
+        }
+    }
+    /// whether vblank started since the last call, for handing the finished frame out
+    pub fn take_frame(&mut self) -> bool {
+        std::mem::take(&mut self.frame_complete)
+    }
+    /// the scanline that was just finished, if one was since the last call
+    pub fn take_scanline(&mut self) -> Option<usize> {
+        if std::mem::take(&mut self.scanline_complete) {
+            Some(self.scanline as usize)
+        } else {
+            None
+        }
+    }
+    /// whether the nmi output went high since the last call, for the cpu to start its interrupt
+    pub fn take_nmi(&mut self) -> bool {
+        std::mem::take(&mut self.nmi_pending)
+    }
+    /// the nmi output is high while both vblank and the enable bit in $2000 are set,
+    /// the cpu sees an interrupt when it goes from low to high
+    fn update_nmi(&mut self) {
+        let output = self.status.vblank() && self.control.interrupt();
+        if output && !self.nmi_output {
+            self.nmi_pending = true;
+        }
+        self.nmi_output = output;
+    }
+    /// whether an opaque pixel of sprite 0 was drawn over the background this frame
+    pub fn sprite_zero_hit(&self) -> bool {
+        self.status.sprite_zero_hit()
+    }
+    /// whether more than 8 sprites were found on a scanline this frame, give or take the hardware bug
+    pub fn sprite_overflow(&self) -> bool {
+        self.status.sprite_overflow()
+    }
+    /// the color subcarrier phase at the start of the last frame, for the ntsc filter
+    pub fn burst_phase(&self) -> u8 {
+        self.burst_phase
+    }
+    /// the picture from the last frame, 256 by 240. each pixel is a palette index
+    /// with the emphasis bits above it, see Palette for turning them into rgb
+    pub fn framebuffer(&self) -> &[u16] {
+        &self.framebuffer[..]
+    }
+    /// the last frame, with what a video filter needs to know about it
+    pub fn frame_output(&self) -> Frame<'_> {
+        Frame {
+            pixels: self.framebuffer(),
+            burst_phase: self.burst_phase,
+            number: self.frame,
+        }
+    }
+    /// reads from the ppu's own address space: pattern tables, nametables and palettes
+    fn fetch(&mut self, cart: &mut Option<NesCart>, address: u16) -> u8 {
+        match cart {
+            Some(cart) => self.ppu_bus(cart).get(address),
+            None => 0,
+        }
+    }
+    /// whether the ppu is fetching tiles right now, on a visible or pre-render line with rendering enabled
+    fn is_rendering(&self) -> bool {
+        let visible = (0..FRAME_HEIGHT as i16).contains(&self.scanline);
+        let prerender = self.scanline == PRERENDER_SCANLINE;
+        (visible || prerender) && (self.mask.display_bg() || self.mask.display_fg())
+    }
+    /// the work done on each dot while rendering is enabled
+    fn render_tick(&mut self, cart: &mut Option<NesCart>) {
+        if !self.is_rendering() {
+            return;
+        }
+        let visible = self.scanline >= 0;
+        let prerender = self.scanline == PRERENDER_SCANLINE;
+        let dot = self.dot;
+        if (2..258).contains(&dot) || (321..338).contains(&dot) {
+            if self.mask.display_bg() {
+                self.background.shift();
+            }
+            match (dot - 1) % 8 {
+                0 => {
+                    self.background.load();
+                    let address = 0x2000 | (self.vram_address & 0x0fff);
+                    self.background.nametable = self.fetch(cart, address);
+                }
+                2 => {
+                    let v = self.vram_address;
+                    let address = 0x23c0 | (v & 0x0c00) | ((v >> 4) & 0x38) | ((v >> 2) & 0x07);
+                    let mut attribute = self.fetch(cart, address);
+                    // each attribute byte covers 4 by 4 tiles, two bits for each 2 by 2 quadrant
+                    if v & 0b10_0000_0000 > 0 {
+                        attribute >>= 4;
+                    }
+                    if v & 0b10 > 0 {
+                        attribute >>= 2;
+                    }
+                    self.background.attribute = attribute & 0b11;
+                }
+                4 => {
+                    let address = self.background_pattern_address();
+                    self.background.pattern_lo = self.fetch(cart, address);
+                }
+                6 => {
+                    let address = self.background_pattern_address() + 8;
+                    self.background.pattern_hi = self.fetch(cart, address);
+                }
+                7 => {
+                    self.increment_coarse_x();
+                }
+                _ => {}
+            }
+        }
+        if dot == 256 {
+            self.increment_y();
+        }
+        if dot == 257 {
+            self.copy_horizontal_scroll();
+            self.evaluate_sprites(cart);
+        }
+        if prerender && (280..305).contains(&dot) {
+            self.copy_vertical_scroll();
+        }
+        if visible && (1..257).contains(&dot) {
+            let x = dot as usize - 1;
+            let y = self.scanline as usize;
+            let color = self.pixel_color(x) as u16;
+            self.framebuffer[y * FRAME_WIDTH + x] = color | self.mask.emphasis(self.region);
+        }
+    }
+    fn background_pattern_address(&self) -> u16 {
+        let fine_y = (self.vram_address >> 12) & 0b111;
+        self.control.background_pattern_table_address()
+            + self.background.nametable as u16 * 16
+            + fine_y
+    }
+    /// the palette index for the pixel at x, combining the background and sprites
+    /// and checking for sprite 0 hit
+    fn pixel_color(&mut self, x: usize) -> u8 {
+        let show_bg = self.mask.display_bg() && (x >= 8 || self.mask.margin_bg());
+        let (bg_palette, bg_pixel) = if show_bg {
+            self.background.pixel(self.fine_x)
+        } else {
+            (0, 0)
+        };
+        let show_fg = self.mask.display_fg() && (x >= 8 || self.mask.margin_fg());
+        let sprite = if show_fg {
+            self.sprites[..self.sprite_count]
+                .iter()
+                .find(|sprite| sprite.pixel(x) != 0)
+        } else {
+            None
+        };
+        // sprite 0 hits even when another sprite is in front of it, but never on the last column
+        if bg_pixel != 0 && show_fg && x != 255 && !self.status.sprite_zero_hit() {
+            let hit = self.sprites[..self.sprite_count]
+                .iter()
+                .any(|sprite| sprite.sprite_zero && sprite.pixel(x) != 0);
+            if hit {
+                self.status.set_sprite_zero_hit();
+            }
+        }
+        let address = match sprite {
+            Some(sprite) if bg_pixel == 0 || !sprite.behind_background() => {
+                0x3f10 | (sprite.palette() as u16) << 2 | sprite.pixel(x) as u16
+            }
+            // the universal background color
+            _ if bg_pixel == 0 => 0x3f00,
+            _ => 0x3f00 | (bg_palette as u16) << 2 | bg_pixel as u16,
+        };
+        self.pallete_table[palette_offset(address)] & self.mask.palette_mask()
+    }
+    fn sprite_height(&self) -> u16 {
+        match self.control.sprite_size() {
+            SpriteSize::EightByEight => 8,
+            SpriteSize::EightBySixteen => 16,
+        }
+    }
+    /// finds the sprites on the next scanline and fetches their patterns.
+    /// also sets the overflow flag, including the hardware's bug where it
+    /// checks the wrong bytes of oam after finding 8 sprites
+    fn evaluate_sprites(&mut self, cart: &mut Option<NesCart>) {
+        self.sprite_count = 0;
+        if self.scanline == PRERENDER_SCANLINE {
+            // nothing is drawn on the pre-render line, so no sprites show up on scanline 0
+            return;
+        }
+        let height = self.sprite_height();
+        let scanline = self.scanline as u16;
+        let in_range = |y: u8| scanline.wrapping_sub(y as u16) < height;
+        let mut found = 0;
+        let mut n = 0;
+        while n < 64 && found < SPRITES_PER_LINE {
+            if in_range(self.oam[n * 4]) {
+                self.fetch_sprite(cart, n);
+                found += 1;
+            }
+            n += 1;
+        }
+        // the sprites past the 8th, the overflow scan below does not look at them properly
+        let rest = n;
+        let mut m = 0;
+        while n < 64 {
+            if in_range(self.oam[n * 4 + m]) {
+                self.status.set_sprite_overflow();
+                break;
+            }
+            n += 1;
+            // the bug: m should stay at 0, the y coordinate
+            m = (m + 1) & 0b11;
+        }
+        if self.unlimited_sprites {
+            for n in rest..64 {
+                if in_range(self.oam[n * 4]) {
+                    self.fetch_sprite(cart, n);
+                }
+            }
+        }
+    }
+    /// copies sprite n from oam into the list for the next scanline
+    fn fetch_sprite(&mut self, cart: &mut Option<NesCart>, n: usize) {
+        let entry = [
+            self.oam[n * 4],
+            self.oam[n * 4 + 1],
+            self.oam[n * 4 + 2],
+            self.oam[n * 4 + 3],
+        ];
+        let [y, tile, attributes, x] = entry;
+        let height = self.sprite_height();
+        let mut row = (self.scanline as u16).wrapping_sub(y as u16);
+        if attributes & 0b1000_0000 > 0 {
+            // vertical flip
+            row = height - 1 - row;
+        }
+        let address = self.sprite_row_address(tile, row);
+        let mut pattern_lo = self.fetch(cart, address);
+        let mut pattern_hi = self.fetch(cart, address + 8);
+        if attributes & 0b0100_0000 > 0 {
+            // horizontal flip
+            pattern_lo = pattern_lo.reverse_bits();
+            pattern_hi = pattern_hi.reverse_bits();
+        }
+        self.sprites[self.sprite_count] = SpriteFetch {
+            x,
+            attributes,
+            pattern_lo,
+            pattern_hi,
+            sprite_zero: n == 0,
+        };
+        self.sprite_count += 1;
+    }
+    /// the pattern address of a row of a sprite, counting from its top
+    fn sprite_row_address(&self, tile: u8, row: u16) -> u16 {
+        let tile_address = match self.control.sprite_size() {
+            SpriteSize::EightByEight => {
+                self.control.sprite_pattern_table_address() + tile as u16 * 16
+            }
+            SpriteSize::EightBySixteen => {
+                // the low bit picks the table, the top half is the even tile
+                let table = (tile as u16 & 1) * 0x1000;
+                let tile = (tile & 0xfe) as u16 + row / 8;
+                table + tile * 16
+            }
+        };
+        tile_address + (row & 0b111)
+    }
+    /// moves the vram address one tile right, into the next nametable at the edge
+    fn increment_coarse_x(&mut self) {
+        if self.vram_address & 0x001f == 31 {
+            self.vram_address &= !0x001f;
+            self.vram_address ^= 0x0400;
+        } else {
+            self.vram_address += 1;
+        }
+    }
+    /// moves the vram address one pixel down, into the next nametable after row 29
+    fn increment_y(&mut self) {
+        if self.vram_address & 0x7000 != 0x7000 {
+            self.vram_address += 0x1000;
+        } else {
+            self.vram_address &= !0x7000;
+            let mut coarse_y = (self.vram_address & 0x03e0) >> 5;
+            if coarse_y == 29 {
+                coarse_y = 0;
+                self.vram_address ^= 0x0800;
+            } else if coarse_y == 31 {
+                // out of bounds rows wrap without switching nametable
+                coarse_y = 0;
+            } else {
+                coarse_y += 1;
+            }
+            self.vram_address = (self.vram_address & !0x03e0) | (coarse_y << 5);
+        }
+    }
+    fn copy_horizontal_scroll(&mut self) {
+        let mask = 0b000_0100_0001_1111;
+        self.vram_address = (self.vram_address & !mask) | (self.temp_address & mask);
+    }
+    fn copy_vertical_scroll(&mut self) {
+        let mask = 0b111_1011_1110_0000;
+        self.vram_address = (self.vram_address & !mask) | (self.temp_address & mask);
     }
 }
 
+/// dots are numbered 0 to 340 on every scanline
+pub const LAST_DOT: u16 = 340;
+/// the line before the picture, where flags are cleared and the first tiles are fetched
+pub const PRERENDER_SCANLINE: i16 = -1;
+
 pub const PPU_ORIGIN: u16 = 0x2000;
 pub const PPU_END: u16 = 0x4020;
 
@@ -150,146 +655,152 @@ pub const PPU_DATA: u16 = 0x007;
 
 pub const PPU_OAM_DMA: u16 = 0x4014;
 
-impl Bus for Nes2c02 {
-    fn bounds(&self) -> (u16, u16) {
-        unimplemented!()
-    }
-    fn set(&mut self, address: u16, value: u8) {
-        if address < PPU_ORIGIN {
-            // nes base ram
-        } else if address < PPU_END {
-            println!("writing to ppu: 0x{address:x} = 0x{value:x}");
-            match address & 0b111 {
-                PPU_CONTROLLER => {
-                    // controller
-                    if self.temp.is_warm() {
-                        self.control.0 = value;
-                    }
-                    // todo: it is possible for this to generate an instant NMI, if other conditions are met
-                    // add this for maximum glitchiness
-                    self.latch.write(value);
-                }
-                PPU_MASK => {
-                    // mask
-                    self.latch.write(value);
-                    self.mask = MaskRegister(value);
-                }
-                PPU_STATUS => {
-                    // status is readonly, ignore write
-                    self.latch.write(value);
-                }
-                PPU_OAM_ADDRESS => {
-                    // oam address
-                    self.latch.write(value);
-                    self.oam_address = value;
-                }
-                PPU_OAM_DATA => {
-                    // oam data
-                    self.latch.write(value);
-                    self.oam_data = value;
-                }
-                PPU_SCROLL => {
-                    // scroll
-                    self.latch.write(value);
-                    self.scroll = value;
-                }
-                PPU_ADDRESS => {
-                    // address
-                    self.latch.write(value);
-                    match self.address {
-                        AddressRegisterLatch::Unset => {
-                            self.address = AddressRegisterLatch::First(value);
-                        }
-                        AddressRegisterLatch::First(hi) => {
-                            self.address = AddressRegisterLatch::Second(hi, value);
-                        }
-                        AddressRegisterLatch::Second(_, _) => {
-                            self.address = AddressRegisterLatch::First(value);
-                        }
-                    }
+impl Nes2c02 {
+    /// a cpu write to $2000-$3FFF, the registers repeat every 8 bytes
+    pub fn write_register(&mut self, cart: &mut Option<NesCart>, address: u16, value: u8) {
+        // every write fills the latch, even to read only registers
+        self.latch.write(value, 0xff, self.ticks);
+        let address = address & 0b111;
+        let warm_up_only = [PPU_CONTROLLER, PPU_MASK, PPU_SCROLL, PPU_ADDRESS];
+        if !self.temp.is_warm() && warm_up_only.contains(&address) {
+            return;
+        }
+        match address {
+            PPU_CONTROLLER => {
+                self.control.0 = value;
+                // the nametable select bits are the top of the scroll position
+                let nametable = (value as u16 & 0b11) << 10;
+                self.temp_address = (self.temp_address & !0x0c00) | nametable;
+                // enabling nmi during vblank fires one straight away
+                self.update_nmi();
+            }
+            PPU_MASK => {
+                self.mask = MaskRegister(value);
+            }
+            PPU_STATUS => {
+                // status is readonly, ignore write
+            }
+            PPU_OAM_ADDRESS => {
+                self.oam_address = value;
+            }
+            PPU_OAM_DATA => {
+                self.oam[self.oam_address as usize] = value;
+                self.oam_address = self.oam_address.wrapping_add(1);
+            }
+            PPU_SCROLL => {
+                let value = value as u16;
+                if !self.write_toggle {
+                    // x: coarse x into t, the low three bits into fine x
+                    self.temp_address = (self.temp_address & !0x001f) | (value >> 3);
+                    self.fine_x = value as u8 & 0b111;
+                } else {
+                    // y: fine y and coarse y into t
+                    self.temp_address = (self.temp_address & !0x73e0)
+                        | ((value & 0b111) << 12)
+                        | ((value >> 3) << 5);
                 }
-                PPU_DATA => {
-                    self.latch.write(value);
-                    // data not writable
+                self.write_toggle = !self.write_toggle;
+            }
+            PPU_ADDRESS => {
+                let value = value as u16;
+                if !self.write_toggle {
+                    // high byte, the top bit of t is cleared
+                    self.temp_address = (self.temp_address & 0x00ff) | ((value & 0x3f) << 8);
+                } else {
+                    self.temp_address = (self.temp_address & 0xff00) | value;
+                    self.vram_address = self.temp_address;
                 }
-                // todo: this is unreachable in current configuration
-                // PPU_OAM_DMA => {
-                //     // oam dma
-                //     self.latch.write(value);
-                //     self.oam_dma = value;
-                // }
-                _ => {
-                    unreachable!()
+                self.write_toggle = !self.write_toggle;
+            }
+            PPU_DATA => {
+                if let Some(cart) = cart {
+                    let address = self.vram_address & 0x3fff;
+                    self.ppu_bus(cart).set(address, value);
                 }
+                self.increment_vram_address();
             }
-        } else {
-            // other addresses handled by cartridge
-            unimplemented!()
+            _ => unreachable!(),
         }
     }
-    fn get(&mut self, address: u16) -> u8 {
-        if address < PPU_ORIGIN {
-            unimplemented!()
-        } else if address < PPU_END {
-            println!("reading from ppu: 0x{address:x}");
-            match address {
-                PPU_CONTROLLER => {
-                    // controller is writeonly, return latch value
-                    self.latch.read()
-                }
-                PPU_MASK => {
-                    // mask is writeonly, return latch
-                    self.latch.read()
-                }
-                PPU_STATUS => {
-                    // status is only three bits, low bits from latch are read
-                    let value = 
-                    (self.status.0 & 0b1110_0000) | (self.latch.read() & 0b0001_1111);
-                    self.latch.write(value);
+    /// a cpu read from $2000-$3FFF, write only registers return the latch
+    pub fn read_register(&mut self, cart: &mut Option<NesCart>, address: u16) -> u8 {
+        let latch = self.latch.read(self.ticks, self.open_bus_decay_ticks());
+        // the bits of the value the ppu drives, the rest come from the latch
+        let mut driven = 0xff;
+        let value = match address & 0b111 {
+            PPU_STATUS => {
+                // status is only three bits, low bits from latch are read
+                driven = 0b1110_0000;
+                let value = (self.status.0 & 0b1110_0000) | (latch & 0b0001_1111);
+                self.status.clear_vblank();
+                self.update_nmi();
+                self.write_toggle = false;
+                value
+            }
+            PPU_OAM_DATA => {
+                // the unused attribute bits don't exist, they read back as 0
+                let value = self.oam[self.oam_address as usize];
+                if self.oam_address & 0b11 == 2 {
+                    value & 0b1110_0011
+                } else {
                     value
                 }
-                PPU_OAM_ADDRESS => {
-                    // oam address is writeonly, return latch
-                    self.latch.read()
-                }
-                PPU_OAM_DATA => {
-                    // oam data
-                    todo!()
-                }
-                PPU_SCROLL => {
-                    // scroll
-                    todo!()
-                }
-                PPU_ADDRESS => {
-                    // address
-                    match self.address {
-                        AddressRegisterLatch::Unset => {
-                            todo!()
-                        }
-                        AddressRegisterLatch::First(hi) => {
-                            todo!()
-                        }
-                        AddressRegisterLatch::Second(_, _) => {
-                            todo!()
-                        }
-                    }
-                }
-                PPU_DATA => {
-                    // data not writable
-                    todo!()
-                }
-                PPU_OAM_DMA => {
-                    // oam dma
-                    todo!()
-                }
-                _ => {
-                    // todo: implement appropriate mirroring
-                    unimplemented!()
-                }
             }
+            PPU_DATA => {
+                let address = self.vram_address & 0x3fff;
+                let value = if address >= 0x3f00 {
+                    // palette reads skip the buffer, which is filled from the nametable underneath.
+                    // palette entries are 6 bits, the top two come from the latch
+                    let buffered = self.fetch(cart, address - 0x1000);
+                    self.data = buffered;
+                    driven = 0b0011_1111;
+                    let color = self.fetch(cart, address) & self.mask.palette_mask();
+                    color | (latch & 0b1100_0000)
+                } else {
+                    let buffered = self.fetch(cart, address);
+                    std::mem::replace(&mut self.data, buffered)
+                };
+                self.increment_vram_address();
+                value
+            }
+            _ => return latch,
+        };
+        self.latch.write(value, driven, self.ticks);
+        value
+    }
+    /// open_bus_decay in ppu ticks
+    fn open_bus_decay_ticks(&self) -> u64 {
+        let ticks_per_second = self.region.master_clock() as f64 / self.region.ppu_divider() as f64;
+        (self.open_bus_decay.as_secs_f64() * ticks_per_second) as u64
+    }
+    /// whether writes to $2000, $2001, $2005 and $2006 are still ignored after power on or reset
+    pub fn warming_up(&self) -> bool {
+        !self.temp.is_warm()
+    }
+    /// the reset button: the ppu starts warming up again and its registers clear, but
+    /// the memories and the vram address keep their contents, as does the vblank flag.
+    /// a new Nes2c02 is one that was just powered on
+    pub fn reset(&mut self) {
+        self.temp = Temperature::Cold(0);
+        self.control = ControlRegister(0);
+        self.mask = MaskRegister(0);
+        self.write_toggle = false;
+        self.temp_address = 0;
+        self.fine_x = 0;
+        self.data = 0;
+        self.update_nmi();
+    }
+    /// $2007 accesses move along by 1 or 32 depending on $2000.
+    /// while rendering the address is busy being the scroll position, and both scroll increments happen instead
+    fn increment_vram_address(&mut self) {
+        if self.is_rendering() {
+            self.increment_coarse_x();
+            self.increment_y();
         } else {
-            // other addresses handled by cartridge
-            unimplemented!()
+            self.vram_address = self
+                .vram_address
+                .wrapping_add(self.control.vram_address_increment())
+                & 0x7fff;
         }
     }
 }
@@ -297,24 +808,40 @@ impl Bus for Nes2c02 {
 impl Default for Nes2c02 {
     fn default() -> Nes2c02 {
         Nes2c02 {
-            latch: DecayingLatch::Decaying(0),
+            latch: DecayingLatch::default(),
+            open_bus_decay: Duration::from_millis(600),
+            ticks: 0,
             control: ControlRegister(0),
             temp: Temperature::Cold(0),
-            v_blank: false,
             mask: MaskRegister(0),
             status: StatusRegister(0),
             oam_address: 0u8,
-            oam_data: 0u8,
-            scroll: 0u8,
-            address: AddressRegisterLatch::Unset,
             data: 0,
             oam_dma: 0u8,
             timing: 0u8,
             clock_counter: 0u8,
-            frame_clock: 0u16,
+            nmi_output: false,
+            nmi_pending: false,
+            frame_complete: false,
+            scanline_complete: false,
+            dot: 0u16,
+            scanline: 0i16,
+            region: Region::Ntsc,
             pallete_table: [0u8; 32],
             vram: [0u8; 2048],
             oam: [0u8; 256],
+            vram_address: 0u16,
+            temp_address: 0u16,
+            fine_x: 0u8,
+            write_toggle: false,
+            background: BackgroundFetch::default(),
+            sprites: [SpriteFetch::default(); 64],
+            sprite_count: 0,
+            unlimited_sprites: false,
+            framebuffer: Box::new([0u16; FRAME_WIDTH * FRAME_HEIGHT]),
+            frame: 0u64,
+            signal_phase: 0u8,
+            burst_phase: 0u8,
         }
     }
 }
@@ -325,7 +852,36 @@ impl Default for Nes2c02 {
 struct StatusRegister(u8);
 
 impl StatusRegister {
-    // todo: implement this...
+    const VBLANK: u8 = 0b1000_0000;
+    const SPRITE_ZERO_HIT: u8 = 0b0100_0000;
+    const SPRITE_OVERFLOW: u8 = 0b0010_0000;
+    fn vblank(self) -> bool {
+        self.0 & Self::VBLANK > 0
+    }
+    /// set on dot 1 of scanline 241
+    fn set_vblank(&mut self) {
+        self.0 |= Self::VBLANK;
+    }
+    fn sprite_zero_hit(self) -> bool {
+        self.0 & Self::SPRITE_ZERO_HIT > 0
+    }
+    fn set_sprite_zero_hit(&mut self) {
+        self.0 |= Self::SPRITE_ZERO_HIT;
+    }
+    fn sprite_overflow(self) -> bool {
+        self.0 & Self::SPRITE_OVERFLOW > 0
+    }
+    fn set_sprite_overflow(&mut self) {
+        self.0 |= Self::SPRITE_OVERFLOW;
+    }
+    /// cleared by reading $2002, and on the pre-render line
+    fn clear_vblank(&mut self) {
+        self.0 &= !Self::VBLANK;
+    }
+    /// cleared on the pre-render line
+    fn clear_sprite_flags(&mut self) {
+        self.0 &= !(Self::SPRITE_ZERO_HIT | Self::SPRITE_OVERFLOW);
+    }
 }
 
 
@@ -365,6 +921,32 @@ impl MaskRegister {
     fn emphasize_b(self) -> bool {
         self.0 & 0b1000_0000 > 0
     }
+    /// greyscale keeps only the brightness column of each palette entry
+    fn palette_mask(self) -> u8 {
+        if self.greyscale() {
+            0x30
+        } else {
+            0x3f
+        }
+    }
+    /// the emphasis bits in the order a pixel keeps them, red, green then blue
+    fn emphasis(self, region: Region) -> u16 {
+        let (red, green) = match region {
+            Region::Pal => (self.emphasize_g(), self.emphasize_r()),
+            Region::Ntsc | Region::Dendy => (self.emphasize_r(), self.emphasize_g()),
+        };
+        let mut emphasis = 0;
+        if red {
+            emphasis |= EMPHASIS_RED;
+        }
+        if green {
+            emphasis |= EMPHASIS_GREEN;
+        }
+        if self.emphasize_b() {
+            emphasis |= EMPHASIS_BLUE;
+        }
+        emphasis
+    }
 }
 
 #[repr(transparent)]
@@ -390,12 +972,16 @@ impl ControlRegister {
     }
     /// for 8x8 entries, ignored for 8/16
     fn sprite_pattern_table_address(self) -> u16 {
-        (self.0 & 0b1000) as u16
+        match self.0 & 0b1000 {
+            0b0000 => 0x0000,
+            0b1000 => 0x1000,
+            _ => unreachable!(),
+        }
     }
     fn background_pattern_table_address(self) -> u16 {
         match self.0 & 0b1_0000 {
-            0b0_0000 => 0b0000,
-            0b1_0000 => 0b1000,
+            0b0_0000 => 0x0000,
+            0b1_0000 => 0x1000,
             _ => unreachable!(),
         }
     }
@@ -0,0 +1,212 @@
+//! pictures of what is in the ppu's memory, for debuggers and rom hackers.
+//! every view is an rgba image, ready to be scaled up on screen or saved as a png
+use super::*;
+use crate::palette::Palette;
+
+/// the color of the scroll window drawn over the nametables
+const SCROLL_WINDOW_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+/// a plain rgba image, 4 bytes per pixel, rows top to bottom
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// a fully transparent image
+    pub fn new(width: usize, height: usize) -> RgbaImage {
+        RgbaImage {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+        }
+    }
+    pub fn get(&self, x: usize, y: usize) -> [u8; 4] {
+        let offset = (y * self.width + x) * 4;
+        let mut rgba = [0; 4];
+        rgba.copy_from_slice(&self.pixels[offset..offset + 4]);
+        rgba
+    }
+    pub fn set(&mut self, x: usize, y: usize, rgba: [u8; 4]) {
+        let offset = (y * self.width + x) * 4;
+        self.pixels[offset..offset + 4].copy_from_slice(&rgba);
+    }
+    fn set_rgb(&mut self, x: usize, y: usize, rgb: [u8; 3]) {
+        self.set(x, y, [rgb[0], rgb[1], rgb[2], 255]);
+    }
+}
+
+/// a sprite from oam with its attribute byte picked apart
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OamEntry {
+    /// 0 to 63, sprite 0 is the one that sets the sprite 0 hit flag
+    pub index: u8,
+    pub x: u8,
+    /// as stored in oam, the sprite is drawn from the scanline below this
+    pub y: u8,
+    pub tile: u8,
+    /// 0 to 3, the sprite palettes at $3F10-$3F1F
+    pub palette: u8,
+    /// drawn behind non-transparent background pixels
+    pub behind_background: bool,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+}
+
+impl Nes2c02 {
+    /// both pattern tables side by side, 256 by 128, drawn with one of the 8 palettes.
+    /// 0 to 3 are the background palettes and 4 to 7 the sprite palettes
+    pub fn pattern_tables_view(
+        &mut self,
+        cart: &mut Option<NesCart>,
+        palette: u8,
+        colors: &Palette,
+    ) -> RgbaImage {
+        let mut image = RgbaImage::new(256, 128);
+        for table in 0..2 {
+            for tile in 0..256 {
+                let address = table * 0x1000 + tile * 16;
+                let x = table as usize * 128 + (tile as usize % 16) * 8;
+                let y = (tile as usize / 16) * 8;
+                for row in 0..8 {
+                    let pixels = self.tile_row(cart, address + row);
+                    for (column, pixel) in pixels.iter().enumerate() {
+                        let color = self.palette_color(palette & 0b111, *pixel);
+                        image.set_rgb(x + column, y + row as usize, colors.rgb(color));
+                    }
+                }
+            }
+        }
+        image
+    }
+    /// all four nametables as the background pattern table draws them, 512 by 480,
+    /// with the 256 by 240 window the scroll registers point at outlined on top
+    pub fn nametables_view(&mut self, cart: &mut Option<NesCart>, colors: &Palette) -> RgbaImage {
+        let width = FRAME_WIDTH * 2;
+        let height = FRAME_HEIGHT * 2;
+        let mut image = RgbaImage::new(width, height);
+        let pattern_table = self.control.background_pattern_table_address();
+        for nametable in 0..4u16 {
+            let base = 0x2000 + nametable * 0x400;
+            let left = (nametable as usize & 1) * FRAME_WIDTH;
+            let top = (nametable as usize >> 1) * FRAME_HEIGHT;
+            for tile in 0..32 * 30 {
+                let (coarse_x, coarse_y) = (tile % 32, tile / 32);
+                let index = self.fetch(cart, base + tile) as u16;
+                let attribute_address = base + 0x3c0 + (coarse_y / 4) * 8 + coarse_x / 4;
+                let attribute = self.fetch(cart, attribute_address);
+                let shift = ((coarse_y & 2) << 1) | (coarse_x & 2);
+                let palette = (attribute >> shift) & 0b11;
+                for row in 0..8 {
+                    let pixels = self.tile_row(cart, pattern_table + index * 16 + row);
+                    for (column, pixel) in pixels.iter().enumerate() {
+                        let x = left + coarse_x as usize * 8 + column;
+                        let y = top + coarse_y as usize * 8 + row as usize;
+                        image.set_rgb(x, y, colors.rgb(self.palette_color(palette, *pixel)));
+                    }
+                }
+            }
+        }
+        // the scroll position from t and fine x, the window wraps around the edges
+        let t = self.temp_address;
+        let scroll_x =
+            (t & 0x1f) as usize * 8 + self.fine_x as usize + ((t >> 10) & 1) as usize * 256;
+        let scroll_y = ((t >> 5) & 0x1f) as usize * 8
+            + ((t >> 12) & 0b111) as usize
+            + ((t >> 11) & 1) as usize * 240;
+        for offset in 0..FRAME_WIDTH {
+            let x = (scroll_x + offset) % width;
+            image.set(x, scroll_y % height, SCROLL_WINDOW_COLOR);
+            image.set(
+                x,
+                (scroll_y + FRAME_HEIGHT - 1) % height,
+                SCROLL_WINDOW_COLOR,
+            );
+        }
+        for offset in 0..FRAME_HEIGHT {
+            let y = (scroll_y + offset) % height;
+            image.set(scroll_x % width, y, SCROLL_WINDOW_COLOR);
+            image.set((scroll_x + FRAME_WIDTH - 1) % width, y, SCROLL_WINDOW_COLOR);
+        }
+        image
+    }
+    /// the 64 sprites in oam
+    pub fn oam_entries(&self) -> Vec<OamEntry> {
+        self.oam
+            .chunks_exact(4)
+            .enumerate()
+            .map(|(index, entry)| OamEntry {
+                index: index as u8,
+                y: entry[0],
+                tile: entry[1],
+                palette: entry[2] & 0b11,
+                behind_background: entry[2] & 0b0010_0000 > 0,
+                flip_horizontal: entry[2] & 0b0100_0000 > 0,
+                flip_vertical: entry[2] & 0b1000_0000 > 0,
+                x: entry[3],
+            })
+            .collect()
+    }
+    /// the 64 sprites in oam order, 8 to a row, flipped and with transparent pixels left clear.
+    /// 64 by 64, or 64 by 128 with 8x16 sprites
+    pub fn oam_view(&mut self, cart: &mut Option<NesCart>, colors: &Palette) -> RgbaImage {
+        let height = self.sprite_height();
+        let mut image = RgbaImage::new(64, 8 * height as usize);
+        for sprite in self.oam_entries() {
+            let left = (sprite.index as usize % 8) * 8;
+            let top = (sprite.index as usize / 8) * height as usize;
+            for row in 0..height {
+                let source = if sprite.flip_vertical {
+                    height - 1 - row
+                } else {
+                    row
+                };
+                let address = self.sprite_row_address(sprite.tile, source);
+                let pixels = self.tile_row(cart, address);
+                for column in 0..8 {
+                    let pixel = if sprite.flip_horizontal {
+                        pixels[7 - column]
+                    } else {
+                        pixels[column]
+                    };
+                    if pixel != 0 {
+                        let color = self.palette_color(4 + sprite.palette, pixel);
+                        image.set_rgb(left + column, top + row as usize, colors.rgb(color));
+                    }
+                }
+            }
+        }
+        image
+    }
+    /// palette ram as 16 by 2 pixels, background palettes on top and sprite palettes below
+    pub fn palette_view(&self, colors: &Palette) -> RgbaImage {
+        let mut image = RgbaImage::new(16, 2);
+        for entry in 0..32 {
+            let color = self.pallete_table[palette_offset(0x3f00 + entry as u16)];
+            image.set_rgb(entry % 16, entry / 16, colors.rgb(color as u16));
+        }
+        image
+    }
+    /// the 2 bit pixels of one row of a tile, left to right
+    fn tile_row(&mut self, cart: &mut Option<NesCart>, address: u16) -> [u8; 8] {
+        let low = self.fetch(cart, address);
+        let high = self.fetch(cart, address + 8);
+        let mut pixels = [0; 8];
+        for (column, pixel) in pixels.iter_mut().enumerate() {
+            let bit = 7 - column;
+            *pixel = ((low >> bit) & 1) | (((high >> bit) & 1) << 1);
+        }
+        pixels
+    }
+    /// the color of a pixel in one of the 8 palettes, pixel 0 is the backdrop
+    fn palette_color(&self, palette: u8, pixel: u8) -> u16 {
+        let address = if pixel == 0 {
+            0x3f00
+        } else {
+            0x3f00 | (palette as u16) << 2 | pixel as u16
+        };
+        self.pallete_table[palette_offset(address)] as u16
+    }
+}
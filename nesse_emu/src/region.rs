@@ -0,0 +1,117 @@
+//! the timing differences between NTSC, PAL and Dendy consoles
+
+/// which kind of console is being emulated
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Region {
+    /// north america and japan, 60Hz
+    #[default]
+    Ntsc,
+    /// europe and australia, 50Hz
+    Pal,
+    /// the russian famiclone, a PAL clock with NTSC style vblank and apu
+    Dendy,
+}
+
+const NTSC_NOISE_PERIODS: [u16; 16] = [
+    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+const PAL_NOISE_PERIODS: [u16; 16] = [
+    4, 8, 14, 30, 60, 88, 118, 148, 188, 236, 354, 472, 708, 944, 1890, 3778,
+];
+const NTSC_DMC_RATES: [u16; 16] = [
+    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+const PAL_DMC_RATES: [u16; 16] = [
+    398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50,
+];
+/// cpu cycles at which the frame counter steps in 4 step mode, the last one also ends the sequence.
+/// 5 step mode has one more step before the end
+const NTSC_FRAME_COUNTER_STEPS: [u32; 5] = [7457, 14913, 22371, 29829, 37281];
+const PAL_FRAME_COUNTER_STEPS: [u32; 5] = [8313, 16627, 24939, 33253, 41565];
+
+impl Region {
+    /// picks the region from the timing bits of a NES 2.0 header.
+    /// multi-region games run as NTSC
+    pub fn from_nes2_timing(timing: u8) -> Region {
+        match timing & 0b11 {
+            1 => Region::Pal,
+            3 => Region::Dendy,
+            _ => Region::Ntsc,
+        }
+    }
+    /// the master clock frequency in Hz
+    pub fn master_clock(self) -> u32 {
+        match self {
+            Region::Ntsc => 21_477_272,
+            Region::Pal | Region::Dendy => 26_601_712,
+        }
+    }
+    /// cpu cycles after power on or reset until the ppu accepts writes to its registers,
+    /// about when its first vblank ends. dendy's is pal's at its faster cpu clock
+    pub fn ppu_warm_up_cycles(self) -> u32 {
+        match self {
+            Region::Ntsc => 29_658,
+            Region::Pal => 33_132,
+            Region::Dendy => 35_341,
+        }
+    }
+    /// cpu cycles per second
+    pub fn cpu_clock(self) -> f64 {
+        self.master_clock() as f64 / self.cpu_divider() as f64
+    }
+    /// master clock ticks per cpu cycle
+    pub fn cpu_divider(self) -> u8 {
+        match self {
+            Region::Ntsc => 12,
+            Region::Pal => 16,
+            Region::Dendy => 15,
+        }
+    }
+    /// master clock ticks per ppu dot
+    pub fn ppu_divider(self) -> u8 {
+        match self {
+            Region::Ntsc => 4,
+            Region::Pal | Region::Dendy => 5,
+        }
+    }
+    /// the last scanline of a frame, counting from -1 for the pre-render line
+    pub fn last_scanline(self) -> i16 {
+        match self {
+            Region::Ntsc => 260,
+            Region::Pal | Region::Dendy => 310,
+        }
+    }
+    /// the scanline where vblank starts. dendy waits for 51 idle lines first,
+    /// so its vblank is as short as NTSC's
+    pub fn vblank_scanline(self) -> i16 {
+        match self {
+            Region::Ntsc | Region::Pal => 241,
+            Region::Dendy => 291,
+        }
+    }
+    /// only NTSC skips a dot on odd frames
+    pub fn skips_odd_frame_dot(self) -> bool {
+        self == Region::Ntsc
+    }
+    /// timer periods for the noise channel, in cpu cycles
+    pub fn noise_periods(self) -> &'static [u16; 16] {
+        match self {
+            Region::Pal => &PAL_NOISE_PERIODS,
+            Region::Ntsc | Region::Dendy => &NTSC_NOISE_PERIODS,
+        }
+    }
+    /// timer periods for the dmc channel, in cpu cycles
+    pub fn dmc_rates(self) -> &'static [u16; 16] {
+        match self {
+            Region::Pal => &PAL_DMC_RATES,
+            Region::Ntsc | Region::Dendy => &NTSC_DMC_RATES,
+        }
+    }
+    /// cpu cycles at which the apu frame counter steps
+    pub fn frame_counter_steps(self) -> &'static [u32; 5] {
+        match self {
+            Region::Pal => &PAL_FRAME_COUNTER_STEPS,
+            Region::Ntsc | Region::Dendy => &NTSC_FRAME_COUNTER_STEPS,
+        }
+    }
+}
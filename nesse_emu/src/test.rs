@@ -1,4 +1,15 @@
 use super::*;
+use crate::apu::Channel;
+use crate::audio::{self, AudioPipeline, AudioSink, Mixer};
+use crate::cartridge::Mirroring;
+use crate::controller::{AdapterKind, ButtonState};
+use crate::cpu::{Cpu6502, Variant};
+use crate::ntsc::{self, NtscFilter, NtscSetup};
+use crate::palette::{Palette, VideoFilter};
+use crate::ppu::OamEntry;
+use crate::region::Region;
+use crate::video::{Frame, RgbFrameBuffer, VideoSink};
+use std::collections::HashMap;
 
 #[test]
 fn test_0xa9_lda_immediate_load_data() {
@@ -72,3 +83,1582 @@ fn test_inx_overflow() {
     println!("result: {:?}\nexpect: {:?}", regs, expected);
     assert!(regs == expected);
 }
+
+#[test]
+fn test_flat_bus_runs_same_opcodes() {
+    let mut cpu = Cpu6502::new(FlatRam::default());
+    // lda #$c0, tax, inx, sta $8000, brk
+    cpu.set_region(0x0600, &[0xa9, 0xc0, 0xaa, 0xe8, 0x8d, 0x00, 0x80, 0x00]);
+    cpu.set_short(0xfffc, 0x0600);
+    cpu.init();
+    cpu.run();
+    let regs = cpu.dump_registers();
+    let expected = NesRegisters::default()
+        .with_x(0xc0 + 1)
+        .with_a(0xc0)
+        .with_flags_from(0xc0 + 1)
+        .with_pc(0x0607);
+    println!("result: {:?}\nexpect: {:?}", regs, expected);
+    assert_eq!(regs.a, expected.a);
+    assert_eq!(regs.x, expected.x);
+    assert_eq!(regs.pc, expected.pc);
+    // the nes would mirror this write into ram or ignore it; the flat bus keeps it
+    assert_eq!(cpu.get(0x8000), 0xc0);
+}
+
+#[test]
+fn test_decimal_adc_per_variant() {
+    // sed, clc, lda #$58, adc #$46, brk
+    let program = &[0xf8, 0x18, 0xa9, 0x58, 0x69, 0x46, 0x00];
+    let mut nmos = Cpu6502::new(FlatRam::default()).with_variant(Variant::Nmos6502);
+    nmos.set_region(0x0600, program);
+    nmos.set_pc(0x0600);
+    nmos.run();
+    assert_eq!(nmos.get_a(), 0x04);
+    assert!(nmos.status_carry());
+
+    // the 2a03 ignores the decimal flag
+    let mut ricoh = Cpu6502::new(FlatRam::default());
+    ricoh.set_region(0x0600, program);
+    ricoh.set_pc(0x0600);
+    ricoh.run();
+    assert_eq!(ricoh.get_a(), 0x9e);
+    assert!(!ricoh.status_carry());
+}
+
+#[test]
+fn test_decimal_sbc_nmos() {
+    // sed, sec, lda #$12, sbc #$21, brk
+    let mut cpu = Cpu6502::new(FlatRam::default()).with_variant(Variant::Nmos6502);
+    cpu.set_region(0x0600, &[0xf8, 0x38, 0xa9, 0x12, 0xe9, 0x21, 0x00]);
+    cpu.set_pc(0x0600);
+    cpu.run();
+    assert_eq!(cpu.get_a(), 0x91);
+    assert!(!cpu.status_carry());
+}
+
+#[test]
+fn test_65c02_opcodes() {
+    let mut cpu = Cpu6502::new(FlatRam::default()).with_variant(Variant::Wdc65c02);
+    cpu.set_region(
+        0x0600,
+        &[
+            0xa9, 0x55, // lda #$55
+            0x85, 0x10, // sta $10
+            0x64, 0x10, // stz $10
+            0x80, 0x01, // bra +1
+            0xdb, // stp, skipped
+            0x1a, // inc a
+            0xda, // phx
+            0x6c, 0xff, 0x02, // jmp ($02ff)
+        ],
+    );
+    // the nmos bug would read the high byte from $0200 instead of $0300
+    cpu.set_short(0x02fe, 0x0000);
+    cpu.set(0x02ff, 0x00);
+    cpu.set(0x0200, 0x06);
+    cpu.set(0x0300, 0x07);
+    cpu.set(0x0700, 0xdb); // stp
+    cpu.set_sp(STACK_INITIAL);
+    cpu.set_pc(0x0600);
+    cpu.run();
+    assert_eq!(cpu.get(0x10), 0x00);
+    assert_eq!(cpu.get_a(), 0x56);
+    assert_eq!(cpu.get_sp(), 0xfc);
+    assert_eq!(cpu.get_pc(), 0x0701);
+}
+
+/// records the trace of each instruction, stopping the cpu at the first line that
+/// differs from the expected log or once the log runs out
+struct TraceChecker {
+    expected: Vec<String>,
+    actual: Vec<String>,
+}
+
+impl NesPeripheral for TraceChecker {
+    fn tick(&mut self, nes: &mut Nes) {
+        let line = crate::peripherals::trace_line(nes);
+        let index = self.actual.len();
+        if self.expected.get(index) != Some(&line) {
+            nes.cpu.running = false;
+        }
+        self.actual.push(line);
+    }
+}
+
+#[test]
+#[ignore = "needs examples/nestest.nes"]
+fn test_nestest_log_conformance() {
+    // nestest.nes isn't checked in, drop it next to nestest.log and run with --ignored
+    let examples = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/");
+    let rom =
+        std::fs::read(format!("{}nestest.nes", examples)).expect("examples/nestest.nes not found");
+    let log = std::fs::read_to_string(format!("{}nestest.log", examples)).unwrap();
+    let mut checker = TraceChecker {
+        expected: log.lines().map(|line| line.to_string()).collect(),
+        actual: vec![],
+    };
+    {
+        let mut nes = Nes::default().with_peripheral(&mut checker);
+        nes.insert_cartridge(NesCart::from_slice(&rom).unwrap());
+        nes.init();
+        nes.set_pc(0xc000);
+        nes.master_clock_drive();
+    }
+
+    let expected = &checker.expected;
+    let actual = &checker.actual;
+    if let Some(index) = (0..actual.len()).find(|i| expected.get(*i) != Some(&actual[*i])) {
+        let mut report = format!("nestest.log diverges at line {}\n", index + 1);
+        for line in expected[index.saturating_sub(5)..index].iter() {
+            report.push_str(&format!("           {}\n", line));
+        }
+        let missing = "<end of log>".to_string();
+        report.push_str(&format!(
+            "expected:  {}\n",
+            expected.get(index).unwrap_or(&missing)
+        ));
+        report.push_str(&format!("actual:    {}\n", actual[index]));
+        panic!("{}", report);
+    }
+    assert_eq!(
+        actual.len(),
+        expected.len(),
+        "stopped after line {}: {}",
+        actual.len(),
+        actual.last().unwrap()
+    );
+}
+
+/// waits out the last instruction, then runs the one at pc and returns how many cycles it took
+fn instruction_cycles(nes: &mut Nes, op: &str) -> u64 {
+    while nes.cpu.next_tick > nes.cpu.cycles {
+        nes.step();
+    }
+    nes.inject_operation(op);
+    let start = nes.cpu.cycles;
+    nes.step();
+    nes.cpu.next_tick - start
+}
+
+#[test]
+fn test_page_cross_cycles() {
+    let mut nes = Nes::default();
+    // lda $02ff,x
+    for (x, cycles) in [(0, 4), (1, 5)] {
+        nes.inject_registers(NesRegisters::default().with_x(x).with_pc(0x0400));
+        assert_eq!(
+            instruction_cycles(&mut nes, "bd ff 02"),
+            cycles,
+            "x = {}",
+            x
+        );
+    }
+    // lda ($10),y with $02ff at $10
+    nes.set_short(0x0010, 0x02ff);
+    for (y, cycles) in [(0, 5), (1, 6)] {
+        nes.inject_registers(NesRegisters::default().with_pc(0x0400));
+        nes.cpu.registers.y = y;
+        assert_eq!(instruction_cycles(&mut nes, "b1 10"), cycles, "y = {}", y);
+    }
+}
+
+#[test]
+fn test_branch_cycles() {
+    let mut nes = Nes::default();
+    // bne not taken, taken on the same page, and taken onto the next page
+    for (pc, flags, offset, cycles) in [
+        (0x0400, 0, "10", 2),
+        (0x0400, 1, "10", 3),
+        (0x04f0, 1, "20", 4),
+        (0x0410, 1, "e0", 4),
+    ] {
+        nes.inject_registers(NesRegisters::default().with_flags_from(flags).with_pc(pc));
+        let op = format!("d0 {}", offset);
+        assert_eq!(
+            instruction_cycles(&mut nes, &op),
+            cycles,
+            "{} at {:x}",
+            op,
+            pc
+        );
+    }
+}
+
+#[test]
+fn test_ram_mirroring() {
+    let mut nes = Nes::default();
+    nes.set(0x0000, 0x42);
+    nes.set(0x07ff, 0x24);
+    for mirror in [0x0800, 0x1000, 0x1800] {
+        assert_eq!(nes.get(mirror), 0x42);
+        assert_eq!(nes.get(mirror + 0x7ff), 0x24);
+    }
+    nes.set(0x1801, 0x99);
+    assert_eq!(nes.get(0x0001), 0x99);
+}
+
+/// wraps a program at $8000 in an nrom image, with all vectors pointing at its start
+fn nrom_image(program: &[u8]) -> Vec<u8> {
+    nrom_image_with_chr(program, &[])
+}
+
+/// wraps a program at $8000 and pattern tables in an nrom image, with vertical mirroring
+fn nrom_image_with_chr(program: &[u8], chr: &[u8]) -> Vec<u8> {
+    let mut prg = vec![0u8; 0x4000];
+    prg[..program.len()].copy_from_slice(program);
+    for vector in [0x3ffa, 0x3ffc, 0x3ffe] {
+        prg[vector] = 0x00;
+        prg[vector + 1] = 0x80;
+    }
+    let mut image = vec![0x4e, 0x45, 0x53, 0x1a, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    image.extend_from_slice(&prg);
+    let mut chr = chr.to_vec();
+    chr.resize(0x2000, 0);
+    image.extend_from_slice(&chr);
+    image
+}
+
+#[test]
+fn test_blargg_protocol_with_reset() {
+    use crate::peripherals::blargg::{run_test_rom, TestRomResult};
+    #[rustfmt::skip]
+    let mut program = vec![
+        0xad, 0x00, 0x02,       // 8000 lda $0200, set once we have been reset
+        0xd0, 0x1a,             // 8003 bne $801f
+        0xee, 0x00, 0x02,       // 8005 inc $0200
+        0xa9, 0xde, 0x8d, 0x01, 0x60, // 8008 sign $6001-$6003
+        0xa9, 0xb0, 0x8d, 0x02, 0x60,
+        0xa9, 0x61, 0x8d, 0x03, 0x60,
+        0xa9, 0x81, 0x8d, 0x00, 0x60, // 8017 ask for reset
+        0x4c, 0x1c, 0x80,       // 801c jmp $801c
+        0xa2, 0x00,             // 801f ldx #0
+        0xbd, 0x40, 0x80,       // 8021 lda $8040,x
+        0x9d, 0x04, 0x60,       // 8024 sta $6004,x
+        0xf0, 0x04,             // 8027 beq $802d
+        0xe8,                   // 8029 inx
+        0x4c, 0x21, 0x80,       // 802a jmp $8021
+        0xa9, 0x00,             // 802d lda #0
+        0x8d, 0x00, 0x60,       // 802f sta $6000
+        0x4c, 0x32, 0x80,       // 8032 jmp $8032
+    ];
+    program.resize(0x40, 0xea);
+    program.extend_from_slice(b"Passed\n\0");
+    let result = run_test_rom(&nrom_image(&program), 60);
+    assert_eq!(result, Some(TestRomResult::Passed("Passed".to_string())));
+
+    // never signs $6000, so runs out of frames
+    let result = run_test_rom(&nrom_image(&[0x4c, 0x00, 0x80]), 10);
+    assert_eq!(result, Some(TestRomResult::Unfinished(String::new())));
+}
+
+/// runs the ppu alone until the next vblank, when the picture is complete
+fn run_ppu_frame(nes: &mut Nes) {
+    run_ppu_until(nes, 241, 0);
+}
+
+/// runs the ppu alone until it accepts writes to all of its registers
+fn warm_up(nes: &mut Nes) {
+    while nes.ppu.warming_up() {
+        nes.ppu.tick(&mut nes.cartridge);
+    }
+}
+
+/// runs the ppu alone until it reaches the given dot
+fn run_ppu_until(nes: &mut Nes, scanline: i16, dot: u16) {
+    nes.ppu.tick(&mut nes.cartridge);
+    while (nes.ppu.scanline, nes.ppu.dot) != (scanline, dot) {
+        nes.ppu.tick(&mut nes.cartridge);
+    }
+}
+
+#[test]
+fn test_background_rendering() {
+    // tile 1 is solid color 1, tile 2 is solid color 3
+    let mut chr = vec![0u8; 0x30];
+    chr[0x10..0x18].copy_from_slice(&[0xff; 8]);
+    chr[0x20..0x30].copy_from_slice(&[0xff; 16]);
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image_with_chr(&[], &chr)).unwrap());
+    warm_up(&mut nes);
+    nes.ppu.pallete_table[0] = 0x0f;
+    nes.ppu.pallete_table[5] = 0x16;
+    nes.ppu.pallete_table[7] = 0x2a;
+    // top left tile uses tile 1, the one right of it tile 2 with palette 1
+    nes.ppu.vram[0] = 1;
+    nes.ppu.vram[1] = 2;
+    nes.ppu.vram[0x3c0] = 0b01;
+    // show background, including the leftmost 8 pixels
+    nes.set(0x2001, 0b1010);
+    run_ppu_frame(&mut nes);
+    run_ppu_frame(&mut nes);
+    let frame = nes.ppu.framebuffer();
+    assert_eq!(frame.len(), 256 * 240);
+    // the attribute applies to the whole 2x2 tile quadrant
+    assert_eq!(&frame[0..8], &[0x16; 8]);
+    assert_eq!(&frame[8..16], &[0x2a; 8]);
+    assert_eq!(frame[7 * 256 + 7], 0x16);
+    assert_eq!(frame[8 * 256], 0x0f);
+    assert_eq!(frame[16], 0x0f);
+
+    // hide the leftmost 8 pixels, and scroll 4 pixels right
+    nes.set(0x2001, 0b1000);
+    nes.set(0x2005, 4);
+    nes.set(0x2005, 0);
+    run_ppu_frame(&mut nes);
+    let frame = nes.ppu.framebuffer();
+    assert_eq!(&frame[0..8], &[0x0f; 8]);
+    assert_eq!(&frame[8..12], &[0x2a; 4]);
+    assert_eq!(frame[12], 0x0f);
+}
+
+#[test]
+fn test_sprite_rendering() {
+    // tile 1 is solid color 1, tile 2 has only its left column set, in color 2
+    let mut chr = vec![0u8; 0x30];
+    chr[0x10..0x18].copy_from_slice(&[0xff; 8]);
+    chr[0x28..0x30].copy_from_slice(&[0x80; 8]);
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image_with_chr(&[], &chr)).unwrap());
+    warm_up(&mut nes);
+    nes.ppu.pallete_table[0] = 0x0f;
+    nes.ppu.pallete_table[1] = 0x01;
+    nes.ppu.pallete_table[0x11] = 0x11;
+    nes.ppu.pallete_table[0x16] = 0x16;
+    // background tile at column 2, row 2 is solid
+    nes.ppu.vram[2 * 32 + 2] = 1;
+    // sprite 0 behind the background over that tile, sprite 1 flipped horizontally in the open
+    nes.ppu.oam[0..4].copy_from_slice(&[15, 1, 0b0010_0000, 20]);
+    nes.ppu.oam[4..8].copy_from_slice(&[49, 2, 0b0100_0001, 40]);
+    for sprite in 2..64 {
+        nes.ppu.oam[sprite * 4] = 0xff;
+    }
+    nes.set(0x2001, 0b1_1110);
+    run_ppu_frame(&mut nes);
+    run_ppu_frame(&mut nes);
+    let frame = nes.ppu.framebuffer();
+    // sprites are drawn one line below their y coordinate
+    assert_eq!(frame[15 * 256 + 20], 0x0f);
+    assert_eq!(frame[16 * 256 + 20], 0x01);
+    assert_eq!(frame[16 * 256 + 23], 0x01);
+    assert_eq!(frame[16 * 256 + 27], 0x11);
+    assert_eq!(frame[23 * 256 + 27], 0x11);
+    assert_eq!(frame[24 * 256 + 27], 0x0f);
+    assert_eq!(frame[50 * 256 + 40], 0x0f);
+    assert_eq!(frame[50 * 256 + 47], 0x16);
+    assert!(nes.ppu.sprite_zero_hit());
+    assert!(!nes.ppu.sprite_overflow());
+}
+
+#[test]
+fn test_sprite_overflow() {
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image(&[])).unwrap());
+    warm_up(&mut nes);
+    // 9 sprites on one line, every one with a solid tile
+    nes.ppu.oam.fill(0xff);
+    for sprite in 0..9 {
+        nes.ppu.oam[sprite * 4..sprite * 4 + 4].copy_from_slice(&[100, 0, 0, sprite as u8 * 10]);
+    }
+    nes.set(0x2001, 0b1_0110);
+    run_ppu_frame(&mut nes);
+    assert!(nes.ppu.sprite_overflow());
+
+    // 8 sprites, with the ninth byte checked by the bug matching the line
+    nes.ppu.oam.fill(0xf0);
+    for sprite in 0..8 {
+        nes.ppu.oam[sprite * 4] = 100;
+    }
+    // the bug reads the tile byte of sprite 9 as its y coordinate
+    nes.ppu.oam[9 * 4 + 1] = 100;
+    run_ppu_frame(&mut nes);
+    assert!(nes.ppu.sprite_overflow());
+
+    // 9 sprites again, where the bug reads the wrong bytes and misses the ninth
+    nes.ppu.oam.fill(0xf0);
+    for sprite in 0..8 {
+        nes.ppu.oam[sprite * 4] = 100;
+    }
+    nes.ppu.oam[9 * 4] = 100;
+    run_ppu_frame(&mut nes);
+    assert!(!nes.ppu.sprite_overflow());
+}
+
+#[test]
+fn test_unlimited_sprites() {
+    let mut chr = vec![0u8; 0x20];
+    chr[0x10..0x18].copy_from_slice(&[0xff; 8]);
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image_with_chr(&[], &chr)).unwrap());
+    warm_up(&mut nes);
+    nes.ppu.pallete_table[0x11] = 0x11;
+    nes.ppu.oam.fill(0xff);
+    for sprite in 0..10 {
+        nes.ppu.oam[sprite * 4..sprite * 4 + 4].copy_from_slice(&[100, 1, 0, sprite as u8 * 10]);
+    }
+    nes.set(0x2001, 0b1_0100);
+    run_ppu_frame(&mut nes);
+    run_ppu_frame(&mut nes);
+    assert_eq!(nes.ppu.framebuffer()[101 * 256 + 85], 0x00);
+    nes.ppu.unlimited_sprites = true;
+    run_ppu_frame(&mut nes);
+    assert_eq!(nes.ppu.framebuffer()[101 * 256 + 85], 0x11);
+    assert!(nes.ppu.sprite_overflow());
+
+    // the 9th sprite is off the line, so the bugged scan finds no overflow and runs to the end.
+    // the 10th is still drawn
+    nes.ppu.oam.fill(0xff);
+    for sprite in (0..8).chain(9..10) {
+        nes.ppu.oam[sprite * 4..sprite * 4 + 4].copy_from_slice(&[100, 1, 0, sprite as u8 * 10]);
+    }
+    run_ppu_frame(&mut nes);
+    assert_eq!(nes.ppu.framebuffer()[101 * 256 + 95], 0x11);
+    assert!(!nes.ppu.sprite_overflow());
+}
+
+#[test]
+fn test_ppu_registers() {
+    let mut chr = vec![0u8; 0x10];
+    chr[0] = 0xab;
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image_with_chr(&[], &chr)).unwrap());
+    warm_up(&mut nes);
+    // $2006 takes the high byte first, $2007 writes move along by 1
+    nes.set(0x2006, 0x20);
+    nes.set(0x2006, 0x00);
+    nes.set(0x2007, 0x11);
+    nes.set(0x2007, 0x22);
+    // with vertical mirroring $2800 is $2000
+    nes.set(0x2006, 0x28);
+    nes.set(0x2006, 0x00);
+    // the first read returns the stale buffer
+    nes.get(0x2007);
+    assert_eq!(nes.get(0x2007), 0x11);
+    assert_eq!(nes.get(0x2007), 0x22);
+    // pattern table reads go to chr
+    nes.set(0x2006, 0x00);
+    nes.set(0x2006, 0x00);
+    nes.get(0x2007);
+    assert_eq!(nes.get(0x2007), 0xab);
+
+    // increment by 32 from $2000, through a mirror of the registers at $3FF8
+    nes.set(0x3ff8, 0b100);
+    nes.set(0x2006, 0x20);
+    nes.set(0x2006, 0x02);
+    nes.set(0x2007, 0x33);
+    nes.set(0x2007, 0x44);
+    assert_eq!(nes.ppu.vram[0x02], 0x33);
+    assert_eq!(nes.ppu.vram[0x22], 0x44);
+    nes.set(0x2000, 0);
+
+    // palette reads skip the buffer, $3F10 mirrors $3F00
+    nes.set(0x2006, 0x3f);
+    nes.set(0x2006, 0x10);
+    nes.set(0x2007, 0x2c);
+    nes.set(0x2006, 0x3f);
+    nes.set(0x2006, 0x00);
+    assert_eq!(nes.get(0x2007) & 0x3f, 0x2c);
+
+    // reading $2002 resets the write toggle, so a half written address is dropped
+    nes.set(0x2006, 0x12);
+    nes.get(0x2002);
+    nes.set(0x2006, 0x20);
+    nes.set(0x2006, 0x01);
+    nes.get(0x2007);
+    assert_eq!(nes.get(0x2007), 0x22);
+
+    // $2004 writes move oam_address along, reads don't
+    nes.set(0x2003, 0x10);
+    nes.set(0x2004, 0x55);
+    nes.set(0x2004, 0xff);
+    assert_eq!(nes.ppu.oam_address, 0x12);
+    nes.set(0x2003, 0x10);
+    assert_eq!(nes.get(0x2004), 0x55);
+    assert_eq!(nes.get(0x2004), 0x55);
+    // the unused attribute bits read back as 0
+    nes.set(0x2003, 0x12);
+    nes.set(0x2004, 0xff);
+    nes.set(0x2003, 0x12);
+    assert_eq!(nes.get(0x2004), 0xe3);
+}
+
+#[test]
+fn test_oam_dma() {
+    let mut nes = Nes::default();
+    for i in 0..=0xffu8 {
+        nes.set(0x0200 + i as u16, i);
+    }
+    nes.set(0x2003, 0x04);
+    nes.set(0x4014, 0x02);
+    // the copy starts at oam_address and wraps around
+    assert_eq!(nes.ppu.oam[0x04], 0x00);
+    assert_eq!(nes.ppu.oam[0x03], 0xff);
+    assert_eq!(nes.cpu.stall, 513);
+}
+
+#[test]
+fn test_mid_frame_scroll_split() {
+    // tile 1 is solid color 1, tile 2 is solid color 3
+    let mut chr = vec![0u8; 0x30];
+    chr[0x10..0x18].copy_from_slice(&[0xff; 8]);
+    chr[0x20..0x30].copy_from_slice(&[0xff; 16]);
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image_with_chr(&[], &chr)).unwrap());
+    warm_up(&mut nes);
+    nes.ppu.pallete_table[0] = 0x0f;
+    nes.ppu.pallete_table[1] = 0x16;
+    nes.ppu.pallete_table[3] = 0x2a;
+    // a column of tile 1 down the left edge, and tile 2 in the third column of the top row
+    for row in 0..30 {
+        nes.ppu.vram[row * 32] = 1;
+    }
+    nes.ppu.vram[2] = 2;
+    // show background with no scroll, starting in vblank
+    run_ppu_frame(&mut nes);
+    nes.set(0x2000, 0);
+    nes.set(0x2001, 0b1010);
+    nes.get(0x2002);
+    nes.set(0x2005, 0);
+    nes.set(0x2005, 0);
+
+    // the horizontal scroll is copied from t at dot 257,
+    // so a $2005 write before then moves the next line over
+    run_ppu_until(&mut nes, 241, 0);
+    run_ppu_until(&mut nes, 100, 200);
+    nes.set(0x2005, 8);
+    nes.set(0x2005, 0);
+    run_ppu_frame(&mut nes);
+    let frame = nes.ppu.framebuffer();
+    assert_eq!(frame[100 * 256], 0x16);
+    assert_eq!(frame[101 * 256], 0x0f);
+
+    // a $2006 write in hblank sets the vertical position right away,
+    // so the top row of tiles is drawn again. the address is the scroll position,
+    // 0 is the top left of the first nametable with a fine y of 0
+    nes.set(0x2005, 0);
+    nes.set(0x2005, 0);
+    run_ppu_until(&mut nes, 120, 300);
+    nes.set(0x2006, 0x00);
+    nes.set(0x2006, 0x00);
+    run_ppu_frame(&mut nes);
+    let frame = nes.ppu.framebuffer();
+    assert_eq!(frame[16], 0x2a);
+    assert_eq!(frame[120 * 256 + 16], 0x0f);
+    assert_eq!(frame[121 * 256 + 16], 0x2a);
+    assert_eq!(frame[128 * 256 + 16], 0x2a);
+    assert_eq!(frame[129 * 256 + 16], 0x0f);
+}
+
+#[test]
+fn test_nametable_mirroring() {
+    use Mirroring::*;
+    let tables = [0x2000, 0x2400, 0x2800, 0x2c00];
+    let banks =
+        |mirroring: Mirroring| tables.map(|table| mirroring.nametable_offset(table + 5) >> 10);
+    assert_eq!(banks(Horizontal), [0, 0, 1, 1]);
+    assert_eq!(banks(Vertical), [0, 1, 0, 1]);
+    assert_eq!(banks(SingleScreenLower), [0, 0, 0, 0]);
+    assert_eq!(banks(SingleScreenUpper), [1, 1, 1, 1]);
+    assert_eq!(banks(FourScreen), [0, 1, 2, 3]);
+    assert_eq!(Vertical.nametable_offset(0x3c05), 0x405);
+}
+
+/// writes bytes through $2006/$2007
+fn ppu_write(nes: &mut Nes, address: u16, values: &[u8]) {
+    nes.set(0x2006, (address >> 8) as u8);
+    nes.set(0x2006, address as u8);
+    for value in values {
+        nes.set(0x2007, *value);
+    }
+}
+
+/// reads a byte through $2006/$2007, skipping the buffered read
+fn ppu_read(nes: &mut Nes, address: u16) -> u8 {
+    nes.set(0x2006, (address >> 8) as u8);
+    nes.set(0x2006, address as u8);
+    nes.get(0x2007);
+    nes.get(0x2007)
+}
+
+#[test]
+fn test_ppu_bus() {
+    // chr rom ignores writes
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image_with_chr(&[], &[0x12])).unwrap());
+    warm_up(&mut nes);
+    ppu_write(&mut nes, 0x0000, &[0x34]);
+    assert_eq!(ppu_read(&mut nes, 0x0000), 0x12);
+
+    // no chr banks means chr ram, and horizontal mirroring from the header
+    let mut image = nrom_image(&[]);
+    image[5] = 0;
+    image[6] = 0;
+    image.truncate(16 + 0x4000);
+    let cart = NesCart::from_slice(&image).unwrap();
+    assert_eq!(cart.mirroring(), Mirroring::Horizontal);
+    let mut nes = Nes::default();
+    nes.insert_cartridge(cart);
+    warm_up(&mut nes);
+    ppu_write(&mut nes, 0x1ff0, &[0x34, 0x56]);
+    assert_eq!(ppu_read(&mut nes, 0x1ff0), 0x34);
+    assert_eq!(ppu_read(&mut nes, 0x1ff1), 0x56);
+    ppu_write(&mut nes, 0x2400, &[0x78]);
+    assert_eq!(ppu_read(&mut nes, 0x2000), 0x78);
+    assert_eq!(ppu_read(&mut nes, 0x2800), 0x00);
+
+    // four screen gives every nametable its own ram
+    image[6] = 0b1000;
+    let cart = NesCart::from_slice(&image).unwrap();
+    assert_eq!(cart.mirroring(), Mirroring::FourScreen);
+    let mut nes = Nes::default();
+    nes.insert_cartridge(cart);
+    warm_up(&mut nes);
+    for (i, table) in [0x2000, 0x2400, 0x2800, 0x2c00].into_iter().enumerate() {
+        ppu_write(&mut nes, table + 0x2bf, &[i as u8 + 1]);
+    }
+    for (i, table) in [0x2000, 0x2400, 0x2800, 0x2c00].into_iter().enumerate() {
+        assert_eq!(ppu_read(&mut nes, table + 0x2bf), i as u8 + 1);
+        assert_eq!(ppu_read(&mut nes, table + 0x12bf), i as u8 + 1);
+    }
+
+    // the backdrop entries of the sprite palettes mirror the background ones
+    ppu_write(&mut nes, 0x3f00, &[0x01, 0x02, 0x03, 0x04]);
+    ppu_write(&mut nes, 0x3f14, &[0x05, 0x06]);
+    for (address, value) in [
+        (0x3f10, 0x01),
+        (0x3f04, 0x05),
+        (0x3f15, 0x06),
+        (0x3fe1, 0x02),
+    ] {
+        // palette reads skip the buffer
+        nes.set(0x2006, (address >> 8) as u8);
+        nes.set(0x2006, address as u8);
+        assert_eq!(nes.get(0x2007) & 0x3f, value);
+    }
+}
+
+#[test]
+fn test_vblank_timing() {
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image(&[])).unwrap());
+    run_ppu_until(&mut nes, 241, 1);
+    assert_eq!(nes.get(0x2002) & 0x80, 0);
+    run_ppu_until(&mut nes, 241, 2);
+    // reading $2002 clears the flag
+    assert_eq!(nes.get(0x2002) & 0x80, 0x80);
+    assert_eq!(nes.get(0x2002) & 0x80, 0);
+    run_ppu_until(&mut nes, 260, 340);
+    nes.ppu.tick(&mut nes.cartridge);
+    assert_eq!((nes.ppu.scanline, nes.ppu.dot), (-1, 0));
+    run_ppu_frame(&mut nes);
+
+    // with rendering off every frame is 341 * 262 dots
+    let count_frame = |nes: &mut Nes| {
+        let mut dots = 1;
+        nes.ppu.tick(&mut nes.cartridge);
+        while (nes.ppu.scanline, nes.ppu.dot) != (241, 0) {
+            nes.ppu.tick(&mut nes.cartridge);
+            dots += 1;
+        }
+        dots
+    };
+    assert_eq!(count_frame(&mut nes), 341 * 262);
+    assert_eq!(count_frame(&mut nes), 341 * 262);
+    // with it on, odd frames are one dot shorter
+    nes.set(0x2001, 0b1000);
+    let frames = [count_frame(&mut nes), count_frame(&mut nes)];
+    assert!(frames.contains(&(341 * 262)));
+    assert!(frames.contains(&(341 * 262 - 1)));
+}
+
+#[test]
+fn test_vblank_nmi() {
+    let program = [
+        0x2c, 0x02, 0x20, // bit $2002, wait for a vblank while the ppu warms up
+        0x10, 0xfb, // bpl
+        0x2c, 0x02, 0x20, // bit $2002, and another
+        0x10, 0xfb, // bpl
+        0xa9, 0x80, // lda #$80
+        0x8d, 0x00, 0x20, // sta $2000, enable nmi
+        0x4c, 0x0f, 0x80, // jmp $800f
+        0xe6, 0x10, // nmi: inc $10
+        0x40, // rti
+    ];
+    let mut image = nrom_image(&program);
+    image[16 + 0x3ffa] = 0x12;
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&image).unwrap());
+    nes.init();
+    // nmi is enabled during frame 1's vblank, after the flag was read, so 2, 3 and 4 interrupt
+    while nes.ppu.frame < 5 {
+        nes.master_tick();
+    }
+    assert_eq!(nes.get(0x10), 3);
+    assert_eq!(nes.get_pc(), 0x800f);
+    // the interrupt pushed pc and p, with b clear, which rti took back off
+    assert_eq!(nes.get(0x1fb) & 0b0011_0000, 0b0010_0000);
+    assert_eq!(nes.get_short(0x1fc), 0x800f);
+}
+
+#[test]
+fn test_region_from_nes2_header() {
+    let mut image = nrom_image(&[]);
+    assert_eq!(NesCart::from_slice(&image).unwrap().region(), None);
+    // NES 2.0 identifier, then pal timing
+    image[7] = 0b1000;
+    image[12] = 1;
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&image).unwrap());
+    assert_eq!(nes.console_region(), Region::Pal);
+
+    // pal frames are 312 lines of 341 dots, each 5 master ticks
+    let frame = nes.ppu.frame;
+    while nes.ppu.frame == frame {
+        nes.master_tick();
+    }
+    let mut master_ticks = 0;
+    while nes.ppu.frame == frame + 1 {
+        nes.master_tick();
+        master_ticks += 1;
+    }
+    assert_eq!(master_ticks, 341 * 312 * 5);
+
+    // dendy has the same lines, with vblank 50 lines later
+    image[12] = 3;
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&image).unwrap());
+    assert_eq!(nes.console_region(), Region::Dendy);
+    run_ppu_until(&mut nes, 241, 2);
+    assert_eq!(nes.get(0x2002) & 0x80, 0);
+    run_ppu_until(&mut nes, 291, 2);
+    assert_eq!(nes.get(0x2002) & 0x80, 0x80);
+}
+
+#[test]
+fn test_palette() {
+    let palette = Palette::ntsc();
+    assert_eq!(palette.rgb(0x30), [236, 238, 236]);
+    assert_eq!(palette.rgb(0x0f), [0, 0, 0]);
+    // red emphasis darkens green and blue
+    let [r, g, b] = palette.rgb(0x30 | palette::EMPHASIS_RED);
+    assert_eq!(r, 236);
+    assert!(g < 238 && b < 236);
+    // all three darken everything
+    let [r, g, b] = palette.rgb(0x30 | 0b111 << 6);
+    assert!(r < 236 && g < 238 && b < 236);
+
+    let mut pal = vec![0u8; 64 * 3];
+    pal[3..6].copy_from_slice(&[10, 20, 30]);
+    let loaded = Palette::from_pal(&pal).unwrap();
+    assert_eq!(loaded.rgb(1), [10, 20, 30]);
+    assert_eq!(loaded.rgb(1 | palette::EMPHASIS_BLUE), [8, 16, 30]);
+    let mut pal = vec![0u8; 512 * 3];
+    pal[(0x41 * 3)..(0x41 * 3 + 3)].copy_from_slice(&[1, 2, 3]);
+    assert_eq!(Palette::from_pal(&pal).unwrap().rgb(0x41), [1, 2, 3]);
+    assert!(Palette::from_pal(&[0; 100]).is_none());
+}
+
+#[test]
+fn test_greyscale_and_emphasis() {
+    // tile 1 is solid color 1
+    let mut chr = vec![0u8; 0x20];
+    chr[0x10..0x18].copy_from_slice(&[0xff; 8]);
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image_with_chr(&[], &chr)).unwrap());
+    warm_up(&mut nes);
+    nes.ppu.pallete_table[1] = 0x16;
+    nes.ppu.vram[0] = 1;
+    // greyscale and red emphasis
+    nes.set(0x2001, 0b0010_1011);
+    run_ppu_frame(&mut nes);
+    run_ppu_frame(&mut nes);
+    assert_eq!(nes.ppu.framebuffer()[0], 0x10 | palette::EMPHASIS_RED);
+    // greyscale applies to palette reads too
+    nes.set(0x2006, 0x3f);
+    nes.set(0x2006, 0x01);
+    assert_eq!(nes.get(0x2007) & 0x3f, 0x10);
+    // $2006 moved the scroll position, put it back
+    nes.set(0x2006, 0x00);
+    nes.set(0x2006, 0x00);
+
+    // pal swaps the red and green bits
+    nes.set_console_region(Region::Pal);
+    run_ppu_frame(&mut nes);
+    assert_eq!(nes.ppu.framebuffer()[0], 0x10 | palette::EMPHASIS_GREEN);
+}
+
+#[test]
+fn test_ntsc_filter() {
+    let mut palette = Palette::ntsc();
+    let mut filter = NtscFilter::new(NtscSetup::svideo());
+    assert_eq!(palette.output_width(), 256);
+    assert_eq!(filter.output_width(), ntsc::NTSC_OUTPUT_WIDTH);
+    let mut rgb = vec![0u8; ntsc::NTSC_OUTPUT_WIDTH * 240 * 3];
+    let middle = (120 * ntsc::NTSC_OUTPUT_WIDTH + 300) * 3;
+
+    // flat grey stays grey, flat red stays red
+    filter.apply(&vec![0x10; 256 * 240], 0, &mut rgb);
+    let [r, g, b] = [rgb[middle], rgb[middle + 1], rgb[middle + 2]];
+    assert!(r == g && g == b && r > 100);
+    filter.apply(&vec![0x16; 256 * 240], 0, &mut rgb);
+    let [r, g, b] = [rgb[middle], rgb[middle + 1], rgb[middle + 2]];
+    assert!(r > 150 && g < 80 && b < 80);
+
+    // stripes of black and white pick up color from the subcarrier on composite,
+    // and which color depends on the phase the frame starts at
+    let stripes: Vec<u16> = (0..256 * 240)
+        .map(|i| if i % 2 == 0 { 0x30 } else { 0x0f })
+        .collect();
+    filter.apply(&stripes, 0, &mut rgb);
+    let svideo = rgb[middle..middle + 3].to_vec();
+    filter.set_setup(NtscSetup::composite());
+    filter.apply(&stripes, 0, &mut rgb);
+    let crawl_0 = rgb[middle..middle + 3].to_vec();
+    filter.apply(&stripes, 4, &mut rgb);
+    let crawl_4 = rgb[middle..middle + 3].to_vec();
+    assert_ne!(svideo, crawl_0);
+    assert_ne!(crawl_0, crawl_4);
+
+    // the plain palette goes through the same api
+    let mut rgb = vec![0u8; 256 * 240 * 3];
+    palette.apply(&vec![0x16; 256 * 240], 0, &mut rgb);
+    assert_eq!(&rgb[0..3], &[152, 34, 32]);
+
+    // the subcarrier phase moves from one frame to the next
+    let mut nes = Nes::default();
+    run_ppu_frame(&mut nes);
+    let first = nes.ppu.burst_phase();
+    run_ppu_frame(&mut nes);
+    assert_ne!(first, nes.ppu.burst_phase());
+}
+
+/// counts what the nes hands to its video sink
+#[derive(Default)]
+struct CountingSink {
+    frames: Vec<u64>,
+    scanlines: usize,
+    last_scanline: usize,
+}
+
+impl VideoSink for CountingSink {
+    fn frame(&mut self, frame: &Frame) {
+        assert_eq!(frame.pixels.len(), ppu::FRAME_WIDTH * ppu::FRAME_HEIGHT);
+        self.frames.push(frame.number);
+    }
+    fn scanline(&mut self, y: usize, pixels: &[u16]) {
+        assert_eq!(pixels.len(), ppu::FRAME_WIDTH);
+        self.scanlines += 1;
+        self.last_scanline = y;
+    }
+}
+
+#[test]
+fn test_video_sink() {
+    let mut sink = CountingSink::default();
+    {
+        let mut nes = Nes::default().with_video_sink(&mut sink);
+        nes.insert_cartridge(NesCart::from_slice(&nrom_image(&[])).unwrap());
+        while nes.ppu.frame < 2 {
+            nes.master_tick();
+        }
+    }
+    assert_eq!(sink.frames, vec![0, 1]);
+    assert_eq!(sink.scanlines, 2 * ppu::FRAME_HEIGHT);
+    assert_eq!(sink.last_scanline, ppu::FRAME_HEIGHT - 1);
+
+    // with rendering off the whole frame is the backdrop color
+    let mut rgb = RgbFrameBuffer::new(Palette::ntsc());
+    {
+        let mut nes = Nes::default().with_video_sink(&mut rgb);
+        nes.insert_cartridge(NesCart::from_slice(&nrom_image(&[])).unwrap());
+        nes.ppu.pallete_table[0] = 0x21;
+        while nes.ppu.frame < 2 {
+            nes.master_tick();
+        }
+    }
+    assert_eq!(rgb.frames(), 2);
+    assert_eq!(rgb.rgb().len(), rgb.width() * rgb.height() * 3);
+    let blue = Palette::ntsc().rgb(0x21);
+    assert!(rgb.rgb().chunks_exact(3).all(|pixel| pixel == blue));
+}
+
+#[test]
+fn test_ppu_debug_views() {
+    // tile 1 is solid color 1, tile 2 has color 3 in its top left pixel only
+    let mut chr = vec![0u8; 0x30];
+    chr[0x10..0x18].copy_from_slice(&[0xff; 8]);
+    chr[0x20] = 0x80;
+    chr[0x28] = 0x80;
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image_with_chr(&[], &chr)).unwrap());
+    nes.ppu.pallete_table[0] = 0x0f;
+    nes.ppu.pallete_table[1] = 0x16;
+    nes.ppu.pallete_table[0x17] = 0x2a;
+    let colors = Palette::ntsc();
+
+    let patterns = nes.ppu.pattern_tables_view(&mut nes.cartridge, 0, &colors);
+    assert_eq!((patterns.width, patterns.height), (256, 128));
+    let [r, g, b] = colors.rgb(0x16);
+    assert_eq!(patterns.get(8, 0), [r, g, b, 255]);
+    let [r, g, b] = colors.rgb(0x0f);
+    assert_eq!(patterns.get(0, 0), [r, g, b, 255]);
+
+    // tile 1 in the top left of the second nametable, scrolled to 8,0 of the first
+    nes.ppu.vram[0x400] = 1;
+    nes.ppu.temp_address = 1;
+    let nametables = nes.ppu.nametables_view(&mut nes.cartridge, &colors);
+    assert_eq!((nametables.width, nametables.height), (512, 480));
+    let [r, g, b] = colors.rgb(0x16);
+    assert_eq!(nametables.get(257, 1), [r, g, b, 255]);
+    let [r, g, b] = colors.rgb(0x0f);
+    assert_eq!(nametables.get(1, 1), [r, g, b, 255]);
+    // the scroll window's left edge, and its right edge in the next nametable
+    assert_eq!(nametables.get(8, 100), [255, 0, 255, 255]);
+    assert_eq!(nametables.get(263, 100), [255, 0, 255, 255]);
+    assert_ne!(nametables.get(9, 100), [255, 0, 255, 255]);
+
+    // sprite 1 is tile 2 with palette 1, flipped horizontally and behind the background
+    nes.ppu.oam[4..8].copy_from_slice(&[0x20, 2, 0b0110_0001, 0x40]);
+    let entries = nes.ppu.oam_entries();
+    assert_eq!(entries.len(), 64);
+    let expected = OamEntry {
+        index: 1,
+        x: 0x40,
+        y: 0x20,
+        tile: 2,
+        palette: 1,
+        behind_background: true,
+        flip_horizontal: true,
+        flip_vertical: false,
+    };
+    assert_eq!(entries[1], expected);
+    let sprites = nes.ppu.oam_view(&mut nes.cartridge, &colors);
+    assert_eq!((sprites.width, sprites.height), (64, 64));
+    let [r, g, b] = colors.rgb(0x2a);
+    assert_eq!(sprites.get(15, 0), [r, g, b, 255]);
+    assert_eq!(sprites.get(8, 0), [0, 0, 0, 0]);
+
+    // $3F10 mirrors $3F00
+    let palettes = nes.ppu.palette_view(&colors);
+    assert_eq!((palettes.width, palettes.height), (16, 2));
+    assert_eq!(palettes.get(0, 1), palettes.get(0, 0));
+    let [r, g, b] = colors.rgb(0x2a);
+    assert_eq!(palettes.get(7, 1), [r, g, b, 255]);
+}
+
+#[test]
+fn test_ppu_warm_up_and_open_bus() {
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image(&[])).unwrap());
+    // $2005 writes are ignored for the first 29658 cpu cycles, 3 ppu ticks each
+    nes.set(0x2005, 0xff);
+    assert_eq!((nes.ppu.temp_address, nes.ppu.write_toggle), (0, false));
+    for _ in 0..29_658 * 3 - 1 {
+        nes.ppu.tick(&mut nes.cartridge);
+    }
+    assert!(nes.ppu.warming_up());
+    nes.ppu.tick(&mut nes.cartridge);
+    assert!(!nes.ppu.warming_up());
+    nes.set(0x2005, 0xff);
+    assert_eq!(nes.ppu.temp_address, 0x1f);
+
+    // reset starts the warm up again but keeps memory
+    nes.ppu.oam[0] = 0x42;
+    nes.reset();
+    assert!(nes.ppu.warming_up());
+    assert_eq!((nes.ppu.temp_address, nes.ppu.fine_x), (0, 0));
+    assert_eq!(nes.ppu.oam[0], 0x42);
+
+    // write only registers read back the latch, which fades a bit at a time.
+    // a $2002 read only drives the top three bits
+    nes.ppu.open_bus_decay = std::time::Duration::from_millis(1);
+    nes.set(0x2002, 0xff);
+    assert_eq!(nes.get(0x2000), 0xff);
+    for _ in 0..3000 {
+        nes.ppu.tick(&mut nes.cartridge);
+    }
+    let status = nes.get(0x2002);
+    assert_eq!(status & 0x1f, 0x1f);
+    for _ in 0..3000 {
+        nes.ppu.tick(&mut nes.cartridge);
+    }
+    assert_eq!(nes.get(0x2000), status & 0xe0);
+    for _ in 0..3000 {
+        nes.ppu.tick(&mut nes.cartridge);
+    }
+    assert_eq!(nes.get(0x2000), 0);
+}
+
+#[test]
+fn test_apu_pulse() {
+    let mut apu = Nes2a03Audio::default();
+    // pulse 1 at 50% duty, constant volume 15, length halted, period 16
+    apu.set(0x4015, 0b01);
+    apu.set(0x4000, 0b1011_1111);
+    apu.set(0x4002, 0x10);
+    apu.set(0x4003, 0x08);
+    assert!(apu.pulse_1.playing());
+    // each step of the sequence lasts 17 apu cycles, or 34 cpu cycles, and half of them are high
+    let mut sum = 0u32;
+    for _ in 0..8 * 34 {
+        apu.tick();
+        sum += apu.pulse_1.output() as u32;
+    }
+    assert_eq!(sum, 4 * 34 * 15);
+    assert!(apu.output() >= 0.0 && apu.output() < 0.3);
+    // disabling the channel clears its length counter
+    apu.set(0x4015, 0);
+    assert!(!apu.pulse_1.playing());
+    assert_eq!(apu.pulse_1.output(), 0);
+
+    // a sweep down by half: pulse 1 goes one further than pulse 2
+    apu.set(0x4015, 0b11);
+    for base in [0x4000, 0x4004] {
+        apu.set(base, 0b1011_1111);
+        apu.set(base + 1, 0b1000_1001);
+        apu.set(base + 2, 0x00);
+        apu.set(base + 3, 0x01);
+    }
+    apu.half_frame();
+    assert_eq!(apu.pulse_1.period(), 0x7f);
+    assert_eq!(apu.pulse_2.period(), 0x80);
+
+    // a period under 8 is silent
+    apu.set(0x4002, 0x07);
+    apu.set(0x4003, 0x00);
+    for _ in 0..64 {
+        apu.tick();
+        assert_eq!(apu.pulse_1.output(), 0);
+    }
+
+    // the envelope starts at 15 and steps down every volume + 1 quarter frames
+    apu.set(0x4000, 0b1000_0011);
+    apu.set(0x4003, 0x08);
+    apu.quarter_frame();
+    assert_eq!(apu.pulse_1.volume(), 15);
+    for _ in 0..4 {
+        apu.quarter_frame();
+    }
+    assert_eq!(apu.pulse_1.volume(), 14);
+    // the length counter runs down without the halt flag, 254 half frames
+    for _ in 0..253 {
+        apu.half_frame();
+    }
+    assert!(apu.pulse_1.playing());
+    apu.half_frame();
+    assert!(!apu.pulse_1.playing());
+}
+
+#[test]
+fn test_apu_triangle_and_noise() {
+    let mut apu = Nes2a03Audio::default();
+    // triangle with the linear counter held on, period 16
+    apu.set(0x4015, 0b0100);
+    apu.set(0x4008, 0b1000_0001);
+    apu.set(0x400a, 0x10);
+    apu.set(0x400b, 0x08);
+    apu.quarter_frame();
+    let mut levels = Vec::new();
+    for _ in 0..32 * 17 {
+        apu.tick();
+        levels.push(apu.triangle.output());
+    }
+    assert_eq!(levels.iter().min(), Some(&0));
+    assert_eq!(levels.iter().max(), Some(&15));
+    // letting the linear counter run out holds the level instead of silencing it
+    apu.set(0x4008, 0);
+    apu.quarter_frame();
+    apu.quarter_frame();
+    let held = apu.triangle.output();
+    for _ in 0..100 {
+        apu.tick();
+        assert_eq!(apu.triangle.output(), held);
+    }
+    // an ultrasonic period plays the middle of the wave
+    apu.set(0x400a, 0x01);
+    apu.set(0x400b, 0x08);
+    assert_eq!(apu.triangle.output(), 7);
+
+    // noise at the shortest period, 4 cpu cycles per shift
+    let noise = |short: bool| {
+        let mut apu = Nes2a03Audio::default();
+        apu.set(0x4015, 0b1000);
+        apu.set(0x400c, 0b11_1111);
+        apu.set(0x400e, (short as u8) << 7);
+        apu.set(0x400f, 0x08);
+        let mut samples = Vec::new();
+        for _ in 0..400 {
+            apu.tick();
+            samples.push(apu.noise.output());
+            for _ in 0..3 {
+                apu.tick();
+            }
+        }
+        samples
+    };
+    let long = noise(false);
+    assert!(long.contains(&0) && long.contains(&15));
+    assert!((0..300).any(|i| long[i] != long[i + 93]));
+    // short mode repeats every 93 steps
+    let short = noise(true);
+    assert!(short.contains(&0) && short.contains(&15));
+    assert!((0..300).all(|i| short[i] == short[i + 93]));
+}
+
+#[test]
+fn test_apu_dmc() {
+    let mut apu = Nes2a03Audio::default();
+    // the fastest rate, a 1 byte sample at $C000, starting from level $40
+    apu.set(0x4010, 0x0f);
+    apu.set(0x4011, 0x40);
+    apu.set(0x4012, 0x00);
+    apu.set(0x4013, 0x00);
+    apu.set(0x4015, 0b1_0000);
+    assert_eq!(apu.dmc.dma_request(), Some(0xc000));
+    apu.dmc.dma_fill(0xff);
+    assert_eq!(apu.dmc.dma_request(), None);
+    assert!(!apu.dmc.playing());
+    // the first timer clock loads the byte, the next 8 each add 2
+    for _ in 0..1 + 8 * 54 {
+        apu.tick();
+    }
+    assert_eq!(apu.dmc.output(), 0x50);
+
+    // looping starts the sample over
+    apu.set(0x4010, 0x4f);
+    apu.set(0x4015, 0b1_0000);
+    apu.dmc.dma_fill(0x00);
+    assert!(apu.dmc.playing());
+    assert!(!apu.irq());
+
+    // the irq fires when a sample ends, and writing $4015 acknowledges it
+    apu.set(0x4010, 0x8f);
+    apu.set(0x4015, 0);
+    apu.set(0x4015, 0b1_0000);
+    apu.dmc.dma_fill(0x00);
+    assert!(apu.irq());
+    apu.set(0x4015, 0);
+    assert!(!apu.irq());
+}
+
+#[test]
+fn test_dmc_dma_and_irq() {
+    let program = [
+        0xa9, 0x8f, // lda #$8f, irq on at the fastest rate
+        0x8d, 0x10, 0x40, // sta $4010
+        0xa9, 0x00, // lda #$00, a 1 byte sample at $C000
+        0x8d, 0x12, 0x40, // sta $4012
+        0x8d, 0x13, 0x40, // sta $4013
+        0xa9, 0x10, // lda #$10
+        0x8d, 0x15, 0x40, // sta $4015, start the sample
+        0x58, // cli
+        0x4c, 0x13, 0x80, // jmp $8013
+        0xe6, 0x10, // irq: inc $10
+        0xa9, 0x00, // lda #$00
+        0x8d, 0x15, 0x40, // sta $4015, acknowledge
+        0x40, // rti
+    ];
+    let mut image = nrom_image(&program);
+    image[16 + 0x3ffe] = 0x16;
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&image).unwrap());
+    nes.init();
+    for _ in 0..200 {
+        nes.step();
+    }
+    assert_eq!(nes.get(0x10), 1);
+    assert_eq!(nes.get_pc(), 0x8013);
+    assert!(!nes.apu.irq());
+}
+
+#[test]
+fn test_dmc_dma_stall() {
+    let mut nes = Nes::default();
+    nes.insert_cartridge(NesCart::from_slice(&nrom_image(&[])).unwrap());
+    warm_up(&mut nes);
+    // with the apu counting along from 0, the get cycles are the even ones
+    let start = nes.cpu.cycles & !1;
+    // a 4 cycle read: the halt and the dummy cycle, then a read on a get cycle, lined up or not
+    nes.instruction = InstructionCycles {
+        start,
+        end: start + 4,
+        dma_end: start + 4,
+        writes: (0, 0),
+    };
+    (nes.cpu.cycles, nes.apu.cycles) = (start + 1, start + 1);
+    assert_eq!(nes.dmc_dma_stall(), 4);
+    (nes.cpu.cycles, nes.apu.cycles) = (start + 2, start + 2);
+    assert_eq!(nes.dmc_dma_stall(), 3);
+    // jsr pushing the return address, the halt waits for the writes and overlaps them
+    nes.instruction = InstructionCycles {
+        start,
+        end: start + 6,
+        dma_end: start + 6,
+        writes: write_cycles(0x20, 7, 6),
+    };
+    (nes.cpu.cycles, nes.apu.cycles) = (start + 3, start + 3);
+    assert_eq!(nes.dmc_dma_stall(), 2);
+    // in the middle of oam dma
+    nes.instruction.dma_end = start + 6 + 513;
+    (nes.cpu.cycles, nes.apu.cycles) = (start + 100, start + 100);
+    assert_eq!(nes.dmc_dma_stall(), 2);
+
+    // halted on the last cycle of lda $4016 the controller is read twice and b is lost
+    nes.set_buttons(
+        0,
+        ButtonState {
+            a: true,
+            select: true,
+            ..Default::default()
+        },
+    );
+    nes.set(0x4016, 1);
+    nes.set(0x4016, 0);
+    assert_eq!(nes.get(0x4016) & 1, 1);
+    nes.instruction = InstructionCycles {
+        start,
+        end: start + 4,
+        dma_end: start + 4,
+        writes: (0, 0),
+    };
+    (nes.cpu.cycles, nes.apu.cycles) = (start + 3, start + 3);
+    nes.dmc_dma_stall();
+    assert_eq!(nes.get(0x4016) & 1, 1);
+
+    // and on lda $2007 the vram address moves along one more
+    for (address, value) in [
+        (0x2006, 0x20),
+        (0x2006, 0x00),
+        (0x2007, 0x11),
+        (0x2007, 0x22),
+    ] {
+        nes.set(address, value);
+    }
+    nes.set(0x2006, 0x20);
+    nes.set(0x2006, 0x00);
+    nes.get(0x2007);
+    nes.dmc_dma_stall();
+    assert_eq!(nes.get(0x2007), 0x22);
+}
+
+#[test]
+fn test_apu_frame_counter() {
+    // 4 step mode from an even cycle restarts after 3 cycles, and the irq flag
+    // goes up 29828 cycles into the sequence
+    let mut apu = Nes2a03Audio::default();
+    apu.set(0x4017, 0x00);
+    let mut cycles = 0;
+    while !apu.frame_interrupt() {
+        apu.tick();
+        cycles += 1;
+    }
+    assert_eq!(cycles, 3 + 29828);
+    assert!(apu.irq());
+    // reading $4015 reports the flag and clears it
+    assert_eq!(apu.get(0x4015) & 0b0100_0000, 0b0100_0000);
+    assert!(!apu.irq());
+    // it is set again on the next two cycles, then not until the next frame
+    apu.tick();
+    assert!(apu.frame_interrupt());
+    apu.get(0x4015);
+    apu.tick();
+    assert!(apu.frame_interrupt());
+    apu.get(0x4015);
+    apu.tick();
+    assert!(!apu.frame_interrupt());
+
+    // the inhibit flag clears the irq and stops it
+    let mut apu = Nes2a03Audio::default();
+    apu.set(0x4017, 0x40);
+    for _ in 0..2 * 29830 {
+        apu.tick();
+    }
+    assert!(!apu.irq());
+
+    // the length counter is clocked twice per sequence, and $4015 shows it running
+    let mut apu = Nes2a03Audio::default();
+    apu.set(0x4015, 0b0001);
+    apu.set(0x4000, 0b0001_0000);
+    apu.set(0x4003, 0b0001_1000); // length 2
+    apu.set(0x4017, 0x40);
+    assert_eq!(apu.get(0x4015), 0b0001);
+    for _ in 0..3 + 14913 {
+        apu.tick();
+    }
+    assert_eq!(apu.get(0x4015), 0b0001);
+    for _ in 0..29829 - 14913 {
+        apu.tick();
+    }
+    assert_eq!(apu.get(0x4015), 0);
+
+    // 5 step mode clocks the length counter as it restarts, and never sets the irq
+    apu.set(0x4003, 0b0001_1000);
+    apu.set(0x4017, 0x80);
+    for _ in 0..3 {
+        apu.tick();
+    }
+    assert!(apu.pulse_1.playing());
+    for _ in 0..14913 {
+        apu.tick();
+    }
+    assert!(!apu.pulse_1.playing());
+    for _ in 0..2 * 37282 {
+        apu.tick();
+    }
+    assert!(!apu.irq());
+
+    // read through the cpu, bit 5 of $4015 keeps what was last on the bus
+    let mut nes = Nes::default();
+    nes.set(0x0000, 0xff);
+    nes.get(0x0000);
+    assert_eq!(nes.get(0x4015), 0b0010_0000);
+    nes.get(0x0001);
+    assert_eq!(nes.get(0x4015), 0);
+}
+
+/// collects everything sent to it
+#[derive(Default)]
+struct CollectingSink {
+    samples: Vec<f32>,
+    fill: Option<f32>,
+}
+
+impl AudioSink for CollectingSink {
+    fn sample_rate(&self) -> u32 {
+        48000
+    }
+    fn samples(&mut self, samples: &[f32]) {
+        self.samples.extend_from_slice(samples);
+    }
+    fn fill_level(&self) -> Option<f32> {
+        self.fill
+    }
+}
+
+#[test]
+fn test_audio_pipeline() {
+    // the mixer is nonlinear, two channels together are quieter than twice one
+    let mixer = Mixer::default();
+    assert_eq!(mixer.mix([0, 0, 0, 0, 0]), 0.0);
+    assert!(mixer.mix([15, 15, 0, 0, 0]) < 2.0 * mixer.mix([15, 0, 0, 0, 0]));
+    assert!((mixer.mix([15, 15, 15, 15, 127]) - 1.0).abs() < 0.01);
+
+    let clock = Region::Ntsc.cpu_clock();
+    // a tenth of a second of a 1kHz square wave, then one too high to hear
+    let run = |half_period: usize, sink: &mut CollectingSink| {
+        let mut pipeline = AudioPipeline::new(clock, sink.sample_rate(), 1);
+        for frame in 0..6 {
+            for cycle in 0..29830 {
+                let high = ((frame * 29830 + cycle) / half_period) & 1 == 1;
+                pipeline.add_sample(if high { 0.5 } else { 0.0 });
+            }
+            pipeline.end_frame(sink);
+        }
+        pipeline
+    };
+    let mut sink = CollectingSink::default();
+    run(895, &mut sink);
+    let expected = (6.0 * 29830.0 * 48000.0 / clock) as usize;
+    assert!(sink.samples.len().abs_diff(expected) <= 16);
+    let settled = &sink.samples[2400..];
+    assert!(settled.iter().all(|sample| sample.abs() <= 1.0));
+    assert!(settled.iter().any(|sample| *sample > 0.1));
+    // the high pass filters take the dc offset out
+    let mean = settled.iter().sum::<f32>() / settled.len() as f32;
+    assert!(mean.abs() < 0.02);
+
+    // changing level every cycle is far above nyquist, and does not alias back down
+    let mut sink = CollectingSink::default();
+    run(1, &mut sink);
+    assert!(sink.samples[2400..]
+        .iter()
+        .all(|sample| sample.abs() < 0.02));
+
+    // an emptying queue speeds the output up
+    let mut sink = CollectingSink {
+        fill: Some(0.0),
+        ..Default::default()
+    };
+    let pipeline = run(895, &mut sink);
+    assert!(pipeline.speed() > 1.0);
+    assert!(sink.samples.len() > expected);
+
+    let mut ints = Vec::new();
+    audio::samples_to_i16(&[0.0, 1.0, -2.0], &mut ints);
+    assert_eq!(ints, vec![0, i16::MAX, -i16::MAX]);
+
+    // the nes sends a frame's worth of sound with each frame
+    let mut sink = CollectingSink::default();
+    {
+        let mut nes = Nes::default().with_audio_sink(&mut sink);
+        nes.insert_cartridge(NesCart::from_slice(&nrom_image(&[])).unwrap());
+        nes.init();
+        while nes.ppu.frame < 3 {
+            nes.master_tick();
+        }
+    }
+    assert!(sink.samples.len().abs_diff(3 * 800) < 100);
+}
+
+/// keeps the mix and a stem for each channel
+#[derive(Default)]
+struct StemSink {
+    samples: Vec<f32>,
+    stems: HashMap<Channel, Vec<f32>>,
+}
+
+impl AudioSink for StemSink {
+    fn sample_rate(&self) -> u32 {
+        48000
+    }
+    fn samples(&mut self, samples: &[f32]) {
+        self.samples.extend_from_slice(samples);
+    }
+    fn wants_stems(&self) -> bool {
+        true
+    }
+    fn stem(&mut self, channel: Channel, samples: &[f32]) {
+        self.stems
+            .entry(channel)
+            .or_default()
+            .extend_from_slice(samples);
+    }
+}
+
+#[test]
+fn test_apu_channel_mixing() {
+    let mut mixer = Mixer::default();
+    let levels = [15, 0, 15, 0, 0];
+    let both = mixer.mix(levels);
+    mixer.set_muted(Channel::Pulse1, true);
+    assert_eq!(mixer.mix(levels), mixer.mix([0, 0, 15, 0, 0]));
+    mixer.set_muted(Channel::Pulse1, false);
+    mixer.set_solo(Some(Channel::Pulse1));
+    assert_eq!(mixer.gain(Channel::Triangle), 0.0);
+    assert!((mixer.mix(levels) - mixer.mix([15, 0, 0, 0, 0])).abs() < 1e-6);
+    mixer.set_solo(None);
+    assert!((mixer.mix(levels) - both).abs() < 1e-6);
+    // half the volume is half the level going into the nonlinear mixer
+    mixer.set_volume(Channel::Triangle, 0.5);
+    assert!(mixer.mix(levels) < both);
+    assert!(mixer.mix(levels) > mixer.mix([15, 0, 7, 0, 0]));
+    assert_eq!(
+        mixer.mix_alone(Channel::Pulse2, 15),
+        mixer.mix([0, 15, 0, 0, 0])
+    );
+
+    // pulse 1 at 25% duty, constant volume 9, period 0x100
+    let mut apu = Nes2a03Audio::default();
+    apu.set(0x4015, 0b01);
+    apu.set(0x4000, 0b0111_1001);
+    apu.set(0x4002, 0x00);
+    apu.set(0x4003, 0x09);
+    let state = apu.channel_state(Channel::Pulse1);
+    assert_eq!(state.period, 0x100);
+    assert_eq!(state.duty, 1);
+    assert_eq!(state.volume, 9);
+    assert!(state.playing);
+    assert!((state.frequency - 435.5).abs() < 1.0);
+    assert!(!apu.channel_state(Channel::Pulse2).playing);
+
+    // the stems are recorded alongside the mix, muting a channel leaves its stem alone
+    let mut sink = StemSink::default();
+    {
+        let mut nes = Nes::default().with_audio_sink(&mut sink);
+        nes.insert_cartridge(NesCart::from_slice(&nrom_image(&[])).unwrap());
+        nes.init();
+        nes.apu = apu;
+        nes.apu.mixer.set_muted(Channel::Pulse1, true);
+        while nes.ppu.frame < 3 {
+            nes.master_tick();
+        }
+    }
+    assert_eq!(sink.stems.len(), 5);
+    let pulse = &sink.stems[&Channel::Pulse1];
+    assert_eq!(pulse.len(), sink.samples.len());
+    assert!(pulse.iter().any(|sample| sample.abs() > 0.01));
+    // past the pop of the idle triangle starting up
+    assert!(sink.samples[400..].iter().all(|sample| sample.abs() < 0.01));
+    assert!(sink.stems[&Channel::Noise]
+        .iter()
+        .all(|sample| sample.abs() < 0.01));
+}
+
+#[test]
+fn test_controller() {
+    let buttons = ButtonState {
+        a: true,
+        start: true,
+        left: true,
+        ..Default::default()
+    };
+    assert_eq!(buttons.bits(), 0b0100_1001);
+    assert_eq!(ButtonState::from_bits(0b0100_1001), buttons);
+
+    let mut nes = Nes::default();
+    nes.set_buttons(0, buttons);
+    // no port or adapter reads past player 3
+    nes.set_buttons(4, buttons);
+    nes.set_buttons(8, buttons);
+    assert!(nes.gamepads[4..].iter().all(|gamepad| gamepad.is_none()));
+    // nothing in the second port
+    assert_eq!(nes.get(0x4017) & 1, 0);
+    // while the strobe is high a is read over and over
+    nes.set(0x4016, 1);
+    for _ in 0..3 {
+        assert_eq!(nes.get(0x4016) & 1, 1);
+    }
+    nes.set(0x4016, 0);
+    let bits: Vec<u8> = (0..10).map(|_| nes.get(0x4016) & 1).collect();
+    assert_eq!(bits, vec![1, 0, 0, 1, 0, 0, 1, 0, 1, 1]);
+
+    // lda $4016 leaves the high byte of the address on the top bits
+    nes.set(0x4016, 1);
+    nes.set(0x4016, 0);
+    nes.inject_operation("ad 16 40");
+    nes.step();
+    assert_eq!(nes.cpu.get_a(), 0x41);
+    // a new strobe picks up buttons changed since
+    nes.set_buttons(0, ButtonState::default());
+    nes.set(0x4016, 1);
+    nes.set(0x4016, 0);
+    assert_eq!(nes.get(0x4016) & 1, 0);
+}
+
+/// reads 24 bits from a controller port after a strobe
+fn read_port_bits(nes: &mut Nes, address: u16, bit: u8) -> u32 {
+    nes.set(0x4016, 1);
+    nes.set(0x4016, 0);
+    (0..24).fold(0, |bits, n| {
+        bits | (((nes.get(address) >> bit) & 1) as u32) << n
+    })
+}
+
+#[test]
+fn test_four_player_adapters() {
+    let player = |n: u8| ButtonState::from_bits(1 << n);
+    let mut nes = Nes::default().with_four_player_adapter(AdapterKind::FourScore);
+    for n in 0..4 {
+        nes.set_buttons(n, player(n as u8));
+    }
+    // players 1 and 3, then the signature
+    assert_eq!(read_port_bits(&mut nes, 0x4016, 0), 0x08_04_01);
+    assert_eq!(read_port_bits(&mut nes, 0x4017, 0), 0x04_08_02);
+    // then 1s
+    assert_eq!(nes.get(0x4016) & 1, 1);
+
+    // the hori adapter is on bit 1, with the signatures the other way around
+    let mut nes = Nes::default().with_four_player_adapter(AdapterKind::Hori);
+    nes.set_buttons(2, player(7));
+    assert_eq!(read_port_bits(&mut nes, 0x4016, 1), 0x04_80_00);
+    assert_eq!(read_port_bits(&mut nes, 0x4017, 1), 0x08_00_00);
+    assert_eq!(read_port_bits(&mut nes, 0x4016, 0), 0);
+    assert!(nes
+        .four_player_adapter
+        .is_some_and(|adapter| adapter.kind() == AdapterKind::Hori));
+
+    // unplugged, the third player is not heard
+    nes.four_player_adapter = None;
+    nes.set_buttons(0, player(0));
+    assert_eq!(read_port_bits(&mut nes, 0x4016, 0) & 0xffff, 0xff_01);
+}
@@ -0,0 +1,72 @@
+//! getting pictures out of the nes: a VideoSink is handed every finished frame,
+//! and optionally every scanline as it is drawn
+use crate::palette::VideoFilter;
+use crate::ppu::FRAME_HEIGHT;
+
+/// a finished frame from the ppu
+pub struct Frame<'a> {
+    /// 256 by 240 pixels, each a palette index with the emphasis bits above it
+    pub pixels: &'a [u16],
+    /// the color subcarrier phase the frame started at, for the ntsc filter
+    pub burst_phase: u8,
+    /// how many frames came before this one
+    pub number: u64,
+}
+
+impl<'a> Frame<'a> {
+    /// converts the frame to rgb through a palette or filter, resizing rgb to fit
+    pub fn to_rgb(&self, filter: &mut dyn VideoFilter, rgb: &mut Vec<u8>) {
+        rgb.resize(filter.output_width() * FRAME_HEIGHT * 3, 0);
+        filter.apply(self.pixels, self.burst_phase, rgb);
+    }
+}
+
+/// receives the picture as the ppu makes it, e.g. a window, a video recorder or a test
+pub trait VideoSink {
+    /// called when vblank starts and the frame is complete
+    fn frame(&mut self, frame: &Frame);
+    /// called after each of the 240 visible scanlines is drawn, with its 256 pixels
+    fn scanline(&mut self, _y: usize, _pixels: &[u16]) {}
+}
+
+/// keeps the last frame as rgb, for headless frontends and tests
+pub struct RgbFrameBuffer<F: VideoFilter> {
+    filter: F,
+    rgb: Vec<u8>,
+    frames: u64,
+}
+
+impl<F: VideoFilter> RgbFrameBuffer<F> {
+    pub fn new(filter: F) -> RgbFrameBuffer<F> {
+        let rgb = vec![0; filter.output_width() * FRAME_HEIGHT * 3];
+        RgbFrameBuffer {
+            filter,
+            rgb,
+            frames: 0,
+        }
+    }
+    /// the last frame, 3 bytes per pixel
+    pub fn rgb(&self) -> &[u8] {
+        &self.rgb
+    }
+    pub fn width(&self) -> usize {
+        self.filter.output_width()
+    }
+    pub fn height(&self) -> usize {
+        FRAME_HEIGHT
+    }
+    /// number of frames received
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+    pub fn filter_mut(&mut self) -> &mut F {
+        &mut self.filter
+    }
+}
+
+impl<F: VideoFilter> VideoSink for RgbFrameBuffer<F> {
+    fn frame(&mut self, frame: &Frame) {
+        frame.to_rgb(&mut self.filter, &mut self.rgb);
+        self.frames += 1;
+    }
+}